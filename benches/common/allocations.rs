@@ -0,0 +1,69 @@
+//! Heap usage measurement for the benches and tests registering `CountingAllocator`
+#![allow(dead_code)]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Heap usage of a closure on the current thread
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocationStats {
+    /// Allocations and reallocations
+    pub allocations: usize,
+    /// Largest number of bytes allocated at once, above the usage before the call
+    pub peak_bytes: usize,
+}
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static ALLOCATED_BYTES: Cell<isize> = const { Cell::new(0) };
+    static PEAK_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+fn record_allocation(size: isize) {
+    ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+    record_size_change(size);
+}
+
+fn record_size_change(size_change: isize) {
+    let allocated_bytes = ALLOCATED_BYTES.with(|allocated_bytes| {
+        allocated_bytes.set(allocated_bytes.get() + size_change);
+        allocated_bytes.get()
+    });
+    PEAK_BYTES.with(|peak_bytes| peak_bytes.set(peak_bytes.get().max(allocated_bytes)));
+}
+
+/// System allocator counting the allocations and heap usage of each thread, to be registered
+/// with `#[global_allocator]` so that `measure_allocations` reports them.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record_size_change(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_allocation(new_size as isize - layout.size() as isize);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Runs a closure, returning its output and its heap usage (only counted by `CountingAllocator`)
+pub fn measure_allocations<F, R>(f: F) -> (R, AllocationStats)
+where
+    F: FnOnce() -> R,
+{
+    ALLOCATIONS.with(|allocations| allocations.set(0));
+    ALLOCATED_BYTES.with(|allocated_bytes| allocated_bytes.set(0));
+    PEAK_BYTES.with(|peak_bytes| peak_bytes.set(0));
+    let output = f();
+    let stats = AllocationStats {
+        allocations: ALLOCATIONS.with(|allocations| allocations.get()),
+        peak_bytes: PEAK_BYTES.with(|peak_bytes| peak_bytes.get()) as usize,
+    };
+    (output, stats)
+}
@@ -0,0 +1,24 @@
+//! Model and corpus of the benches and examples
+#![allow(dead_code)]
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// The file at `BPE_MODEL_PATH` if set, the small model of the test fixtures otherwise
+pub fn get_model_path() -> PathBuf {
+    get_fixture_path("BPE_MODEL_PATH", "bpe.model")
+}
+
+/// The file at `BPE_CORPUS_PATH` if set, the generated corpus of the test fixtures otherwise
+pub fn get_corpus_path() -> PathBuf {
+    get_fixture_path("BPE_CORPUS_PATH", "corpus.txt")
+}
+
+fn get_fixture_path(variable: &str, fixture_name: &str) -> PathBuf {
+    match env::var_os(variable) {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(fixture_name),
+    }
+}
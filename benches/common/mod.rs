@@ -0,0 +1,99 @@
+//! Tokenizers and inputs shared by the benches
+pub mod allocations;
+pub mod fixtures;
+
+use bpe_example::{
+    get_file_header, read_model_proto, BacktrackingBpeTokenizer, BpeTokenizer, NaiveBpeTokenizer,
+    NaivePreSplitBpeTokenizer, PriorityQueueBpeLLTokenizer, PriorityQueueBpeTokenizer,
+    PriorityQueuePreSplitBpeLLTokenizer, PriorityQueuePreSplitBpeTokenizer,
+};
+use fixtures::{get_corpus_path, get_model_path};
+
+pub type Tokenize = Box<dyn for<'a> Fn(&'a str) -> Vec<&'a str>>;
+
+fn tokenize_with<T>(tokenizer: T) -> Tokenize
+where
+    T: BpeTokenizer + 'static,
+{
+    Box::new(move |text| tokenizer.tokenize(text))
+}
+
+/// Every algorithm, named as in the benchmark ids
+pub fn get_tokenizers() -> Vec<(&'static str, Tokenize)> {
+    let model_proto = read_model_proto(&get_model_path()).unwrap();
+    vec![
+        (
+            "naive",
+            tokenize_with(NaiveBpeTokenizer::from_model_proto(&model_proto).unwrap()),
+        ),
+        (
+            "priority_queue",
+            tokenize_with(PriorityQueueBpeTokenizer::from_model_proto(&model_proto).unwrap()),
+        ),
+        (
+            "priority_queue_linked_list",
+            tokenize_with(PriorityQueueBpeLLTokenizer::from_model_proto(&model_proto).unwrap()),
+        ),
+        (
+            "backtracking",
+            tokenize_with(BacktrackingBpeTokenizer::from_model_proto(&model_proto).unwrap()),
+        ),
+        (
+            "naive_word_split",
+            tokenize_with(NaivePreSplitBpeTokenizer::from_model_proto(&model_proto).unwrap()),
+        ),
+        (
+            "priority_queue_word_split",
+            tokenize_with(
+                PriorityQueuePreSplitBpeTokenizer::from_model_proto(&model_proto).unwrap(),
+            ),
+        ),
+        (
+            "priority_queue_linked_list_word_split",
+            tokenize_with(
+                PriorityQueuePreSplitBpeLLTokenizer::from_model_proto(&model_proto).unwrap(),
+            ),
+        ),
+    ]
+}
+
+pub fn get_corpus(n_lines: usize) -> String {
+    get_file_header(&get_corpus_path(), n_lines).unwrap()
+}
+
+/// Repeats a pattern up to `length` bytes, cut at a character boundary
+pub fn repeat_to_length(pattern: &str, length: usize) -> String {
+    let mut text = pattern.repeat(length / pattern.len() + 1);
+    let mut end = length;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    text
+}
+
+/// Inputs that are hard for some of the algorithms, as patterns repeated to the input length
+pub fn get_adversarial_patterns() -> Vec<(&'static str, String)> {
+    let corpus = get_corpus(1000);
+    let mut long_words = String::new();
+    let mut word_length = 0;
+    for word in corpus.split_whitespace() {
+        long_words.push_str(word);
+        word_length += word.len();
+        if word_length >= 1024 {
+            long_words.push(' ');
+            word_length = 0;
+        }
+    }
+    vec![
+        ("long_words", long_words),
+        ("no_whitespace", corpus.split_whitespace().collect()),
+        ("repeated_characters", "s".to_string()),
+        (
+            "cjk",
+            "東京は日本の首都です。今日は天気が良いので公園を散歩しました。我们明天去图书馆看书。"
+                .to_string(),
+        ),
+        ("emoji", "😀🎉👍🏽 ❤️🔥 👨‍👩‍👧‍👦🚀✨ 🇯🇵🇫🇷 ".to_string()),
+    ]
+}
@@ -0,0 +1,71 @@
+//! Allocations, peak heap and agenda sizes of a `tokenize` call for every algorithm.
+//!
+//! Run with `cargo bench --bench memory`.
+mod common;
+
+use bpe_example::{
+    read_model_proto, AgendaStats, BpeTokenizer, PriorityQueueBpeLLTokenizer,
+    PriorityQueueBpeTokenizer,
+};
+use common::allocations::{measure_allocations, CountingAllocator};
+use common::fixtures::get_model_path;
+use common::{get_adversarial_patterns, get_corpus, get_tokenizers, repeat_to_length};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Input size in bytes of the adversarial inputs
+const INPUT_LENGTH: usize = 16384;
+
+fn format_agenda_stats(agenda_stats: Option<AgendaStats>) -> String {
+    match agenda_stats {
+        Some(agenda_stats) => format!(
+            "{:>10} {:>10} {:>10}",
+            agenda_stats.pushed, agenda_stats.stale, agenda_stats.peak_len
+        ),
+        None => format!("{:>10} {:>10} {:>10}", "-", "-", "-"),
+    }
+}
+
+fn main() {
+    let model_proto = read_model_proto(&get_model_path()).unwrap();
+    let priority_queue = PriorityQueueBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let priority_queue_linked_list =
+        PriorityQueueBpeLLTokenizer::from_model_proto(&model_proto).unwrap();
+    let tokenizers = get_tokenizers();
+
+    let mut inputs = vec![("lines/1000".to_string(), get_corpus(1000))];
+    for (shape, pattern) in get_adversarial_patterns() {
+        inputs.push((
+            format!("{}/{}", shape, INPUT_LENGTH),
+            repeat_to_length(&pattern, INPUT_LENGTH),
+        ));
+    }
+
+    println!(
+        "{:<26} {:<38} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "input", "algorithm", "bytes", "allocs", "peak KiB", "pushed", "stale", "agenda"
+    );
+    for (input_name, input) in inputs.iter() {
+        for (algorithm, tokenize) in tokenizers.iter() {
+            let (tokens, allocation_stats) = measure_allocations(|| tokenize(input));
+            drop(tokens);
+            let agenda_stats = match *algorithm {
+                "priority_queue" => Some(priority_queue.agenda_stats(input)),
+                "priority_queue_linked_list" => {
+                    Some(priority_queue_linked_list.agenda_stats(input))
+                }
+                _ => None,
+            };
+            println!(
+                "{:<26} {:<38} {:>10} {:>10} {:>10.1} {}",
+                input_name,
+                algorithm,
+                input.len(),
+                allocation_stats.allocations,
+                allocation_stats.peak_bytes as f64 / 1024.0,
+                format_agenda_stats(agenda_stats)
+            );
+        }
+    }
+}
@@ -0,0 +1,79 @@
+#[macro_use]
+extern crate criterion;
+
+mod common;
+
+use bpe_example::{BpeTokenizer, PriorityQueueBpeLLTokenizer};
+use common::fixtures::get_model_path;
+use common::{get_adversarial_patterns, get_corpus, get_tokenizers, repeat_to_length};
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use std::time::Duration;
+
+/// Corpus sizes of the `lines` group
+const LINE_COUNTS: [usize; 4] = [1, 10, 100, 1000];
+
+/// Input sizes in bytes of the adversarial groups
+const INPUT_LENGTHS: [usize; 4] = [256, 1024, 4096, 16384];
+
+fn bench_lines(c: &mut Criterion) {
+    let tokenizers = get_tokenizers();
+    let mut group = c.benchmark_group("lines");
+    for &n_lines in LINE_COUNTS.iter() {
+        let corpus = get_corpus(n_lines);
+        group.throughput(Throughput::Bytes(corpus.len() as u64));
+        for (algorithm, tokenize) in tokenizers.iter() {
+            group.bench_with_input(
+                BenchmarkId::new(*algorithm, n_lines),
+                corpus.as_str(),
+                |b, corpus| b.iter(|| tokenize(black_box(corpus))),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_adversarial(c: &mut Criterion) {
+    let tokenizers = get_tokenizers();
+    for (shape, pattern) in get_adversarial_patterns() {
+        let mut group = c.benchmark_group(shape);
+        group
+            .sample_size(10)
+            .warm_up_time(Duration::from_secs(1))
+            .measurement_time(Duration::from_secs(2));
+        for &length in INPUT_LENGTHS.iter() {
+            let input = repeat_to_length(&pattern, length);
+            group.throughput(Throughput::Bytes(input.len() as u64));
+            for (algorithm, tokenize) in tokenizers.iter() {
+                group.bench_with_input(
+                    BenchmarkId::new(*algorithm, length),
+                    input.as_str(),
+                    |b, input| b.iter(|| tokenize(black_box(input))),
+                );
+            }
+        }
+        group.finish();
+    }
+}
+
+fn bench_pre_processing(c: &mut Criterion) {
+    let tokenizer = PriorityQueueBpeLLTokenizer::new(&get_model_path()).unwrap();
+    let mut group = c.benchmark_group("pre_processing");
+    for &n_lines in LINE_COUNTS.iter() {
+        let corpus = get_corpus(n_lines);
+        group.throughput(Throughput::Bytes(corpus.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(n_lines),
+            corpus.as_str(),
+            |b, corpus| b.iter(|| tokenizer.pre_process_text(black_box(corpus), '\u{2581}', true)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group! {
+name = benches;
+config = Criterion::default();
+targets = bench_lines, bench_adversarial, bench_pre_processing
+}
+
+criterion_main!(benches);
@@ -0,0 +1,15 @@
+#[path = "../benches/common/fixtures.rs"]
+mod fixtures;
+
+use bpe_example::NaiveBpeTokenizer;
+use fixtures::get_model_path;
+use std::env;
+
+fn main() -> anyhow::Result<()> {
+    let text = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "Licensed under the Apache License".to_string());
+    let tokenizer = NaiveBpeTokenizer::new(&get_model_path())?;
+    print!("{}", tokenizer.explain(&text));
+    Ok(())
+}
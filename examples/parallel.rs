@@ -0,0 +1,33 @@
+#[path = "../benches/common/fixtures.rs"]
+mod fixtures;
+
+use bpe_example::{BpeTokenizer, ParallelTokenizer, PriorityQueueBpeLLTokenizer};
+use fixtures::{get_corpus_path, get_model_path};
+use std::fs;
+use std::time::Instant;
+
+fn main() -> anyhow::Result<()> {
+    let corpus_path = get_corpus_path();
+    let model_file = get_model_path();
+
+    let tokenizer = PriorityQueueBpeLLTokenizer::new(&model_file)?;
+    let text = fs::read_to_string(corpus_path)?;
+
+    let start = Instant::now();
+    let sequential_tokens = tokenizer.tokenize(&text);
+    println!("sequential: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let parallel_tokens = ParallelTokenizer::new(&tokenizer)
+        .with_min_chunk_size(4 * 1024)
+        .tokenize(&text);
+    println!("parallel: {:?}", start.elapsed());
+
+    println!(
+        "{} tokens sequentially, {} in parallel",
+        sequential_tokens.len(),
+        parallel_tokens.len()
+    );
+
+    Ok(())
+}
@@ -1,21 +1,18 @@
-use bpe_example::{
-    download_file_to_cache, get_file_header, BpeTokenizer, PriorityQueueBpeLLTokenizer,
-};
+#[path = "../benches/common/fixtures.rs"]
+mod fixtures;
+
+use bpe_example::{get_file_header, BpeTokenizer, PriorityQueueBpeLLTokenizer};
+use fixtures::{get_corpus_path, get_model_path};
 
 fn main() -> anyhow::Result<()> {
-    let hamlet_path = download_file_to_cache(
-        "https://gist.githubusercontent.com/provpup/2fc41686eab7400b796b/raw/b575bd01a58494dfddc1d6429ef0167e709abf9b/hamlet.txt",
-        "hamlet.txt")?;
-    let model_file = download_file_to_cache(
-        "https://huggingface.co/facebook/m2m100_418M/resolve/main/sentencepiece.bpe.model",
-        "bpe.model",
-    )?;
+    let corpus_path = get_corpus_path();
+    let model_file = get_model_path();
 
     let sample_size = 10;
-    let hamlet = get_file_header(&hamlet_path, sample_size)?;
+    let corpus = get_file_header(&corpus_path, sample_size)?;
 
     let tokenizer = PriorityQueueBpeLLTokenizer::new(&model_file)?;
-    let output = tokenizer.tokenize(hamlet.as_str());
+    let output = tokenizer.tokenize(corpus.as_str());
     println!("{:?}", output);
 
     Ok(())
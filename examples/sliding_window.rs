@@ -0,0 +1,22 @@
+#[path = "../benches/common/fixtures.rs"]
+mod fixtures;
+
+use bpe_example::{get_file_header, BpeTokenizer, PriorityQueueBpeLLTokenizer};
+use fixtures::{get_corpus_path, get_model_path};
+
+fn main() -> anyhow::Result<()> {
+    let corpus_path = get_corpus_path();
+    let model_file = get_model_path();
+
+    let sample_size = 10;
+    let corpus = get_file_header(&corpus_path, sample_size)?;
+
+    let tokenizer = PriorityQueueBpeLLTokenizer::new(&model_file)?;
+    for window in tokenizer.encode_windows(corpus.as_str(), 32, 8) {
+        let (start, end) = (window.offsets[0].0, window.offsets[window.len() - 1].1);
+        println!("{:?}", window.ids);
+        println!("{:?}", &corpus[start..end]);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+#[path = "../benches/common/fixtures.rs"]
+mod fixtures;
+
+use bpe_example::{PriorityQueueBpeLLTokenizer, StreamingTokenizer};
+use fixtures::{get_corpus_path, get_model_path};
+use std::fs::File;
+use std::io::BufReader;
+
+fn main() -> anyhow::Result<()> {
+    let corpus_path = get_corpus_path();
+    let model_file = get_model_path();
+
+    let tokenizer = PriorityQueueBpeLLTokenizer::new(&model_file)?;
+    let reader = BufReader::new(File::open(corpus_path)?);
+    let mut n_tokens = 0;
+    for token in StreamingTokenizer::new(&tokenizer, reader) {
+        let _ = token?;
+        n_tokens += 1;
+    }
+    println!("{} tokens", n_tokens);
+
+    Ok(())
+}
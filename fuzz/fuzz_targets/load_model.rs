@@ -0,0 +1,39 @@
+#![no_main]
+
+use bpe_example::{
+    parse_model_proto, BacktrackingBpeTokenizer, BpeTokenizer, Decoder, NaiveBpeTokenizer,
+    PriorityQueueBpeLLTokenizer, PriorityQueuePreSplitBpeLLTokenizer,
+};
+use libfuzzer_sys::fuzz_target;
+
+const TEXT: &str = "Licensed under the (Apache) License, \u{2581}v2.0\tHéllo ﬁne 日本語  ";
+
+fuzz_target!(|data: &[u8]| {
+    let model_proto = match parse_model_proto(data) {
+        Ok(model_proto) => model_proto,
+        Err(_) => return,
+    };
+    // Models with an invalid precompiled charsmap are rejected by every tokenizer
+    let expected = match PriorityQueueBpeLLTokenizer::from_model_proto(&model_proto) {
+        Ok(tokenizer) => tokenizer.encode(TEXT),
+        Err(_) => return,
+    };
+    assert_eq!(
+        NaiveBpeTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode(TEXT),
+        expected
+    );
+    assert_eq!(
+        BacktrackingBpeTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode(TEXT),
+        expected
+    );
+    let _ = PriorityQueuePreSplitBpeLLTokenizer::from_model_proto(&model_proto)
+        .unwrap()
+        .encode(TEXT);
+    if let Ok(decoder) = Decoder::from_model_proto(&model_proto) {
+        let _ = decoder.decode(&expected.ids);
+    }
+});
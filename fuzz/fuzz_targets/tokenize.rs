@@ -0,0 +1,65 @@
+#![no_main]
+
+use bpe_example::{
+    parse_model_proto, BacktrackingBpeTokenizer, BpeTokenizer, Decoder, ModelProto,
+    NaivePreSplitBpeTokenizer, NormalizationForm, PriorityQueueBpeLLTokenizer,
+    PriorityQueueBpeTokenizer, UnicodeNormalizer,
+};
+use libfuzzer_sys::fuzz_target;
+use std::sync::OnceLock;
+
+fn model_proto() -> &'static ModelProto {
+    static MODEL_PROTO: OnceLock<ModelProto> = OnceLock::new();
+    MODEL_PROTO.get_or_init(|| {
+        parse_model_proto(include_bytes!("../../tests/fixtures/bpe.model")).unwrap()
+    })
+}
+
+/// Tokenizers and decoder of the fixture model, built once as building them costs far more than
+/// tokenizing a fuzz input
+struct Fixture {
+    linked_list: PriorityQueueBpeLLTokenizer,
+    b_tree: PriorityQueueBpeTokenizer,
+    backtracking: BacktrackingBpeTokenizer,
+    pre_split: NaivePreSplitBpeTokenizer,
+    normalized: PriorityQueueBpeLLTokenizer,
+    decoder: Decoder,
+}
+
+fn fixture() -> &'static Fixture {
+    static FIXTURE: OnceLock<Fixture> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let model_proto = model_proto();
+        Fixture {
+            linked_list: PriorityQueueBpeLLTokenizer::from_model_proto(model_proto).unwrap(),
+            b_tree: PriorityQueueBpeTokenizer::from_model_proto(model_proto).unwrap(),
+            backtracking: BacktrackingBpeTokenizer::from_model_proto(model_proto).unwrap(),
+            pre_split: NaivePreSplitBpeTokenizer::from_model_proto(model_proto).unwrap(),
+            normalized: PriorityQueueBpeLLTokenizer::from_model_proto(model_proto)
+                .unwrap()
+                .with_normalizer(UnicodeNormalizer::new(NormalizationForm::NFKC)),
+            decoder: Decoder::from_model_proto(model_proto).unwrap(),
+        }
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    let fixture = fixture();
+
+    let tokens = fixture.linked_list.tokenize(text);
+    assert_eq!(tokens.concat(), text);
+    let encoding = fixture.linked_list.encode(text);
+    assert_eq!(fixture.b_tree.encode(text), encoding);
+    assert_eq!(fixture.backtracking.encode(text), encoding);
+    let _ = fixture.pre_split.tokenize(text);
+
+    for (start, end) in fixture.normalized.encode(text).offsets {
+        assert!(text.is_char_boundary(start) && text.is_char_boundary(end) && start <= end);
+    }
+
+    let _ = fixture.decoder.decode(&encoding.ids);
+});
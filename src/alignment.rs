@@ -0,0 +1,54 @@
+/// Maps byte positions of a pre-processed text back to byte positions of the original text.
+///
+/// A symbol `start..end` of the pre-processed text covers `original_start(start)..original_end(end)`
+/// of the original text. Characters produced by a normalizer from a single original character
+/// (e.g. `ﬁ` -> `fi`) all map to the full original character, so that any symbol maps to a valid
+/// slice of the original text.
+///
+/// Offsets are stored in dense tables indexed by pre-processed byte position, making lookups a
+/// single array access.
+#[derive(Debug, Clone, Default)]
+pub struct Alignment {
+    /// Original start of the character containing each byte
+    starts: Vec<usize>,
+    /// Original end of the character ending at each byte, `ends[0]` is unused
+    ends: Vec<usize>,
+}
+
+impl Alignment {
+    /// Registers a pre-processed character spanning `start..end` coming from
+    /// `original_start..original_end`. Characters must be pushed in order.
+    pub fn push(&mut self, start: usize, end: usize, original_start: usize, original_end: usize) {
+        debug_assert_eq!(start, self.starts.len());
+        self.starts.resize(end, original_start);
+        self.ends.resize(end + 1, original_end);
+    }
+
+    /// Registers `length` one-byte characters starting at `start`, coming from the bytes starting
+    /// at `original_start`
+    pub fn push_ascii_run(&mut self, start: usize, original_start: usize, length: usize) {
+        debug_assert_eq!(start, self.starts.len());
+        self.starts.extend(original_start..original_start + length);
+        self.ends.resize(start + 1, original_start);
+        self.ends
+            .extend(original_start + 1..original_start + length + 1);
+    }
+
+    /// Removes all characters, keeping the allocated memory
+    pub fn clear(&mut self) {
+        self.starts.clear();
+        self.ends.clear();
+    }
+
+    pub fn original_start(&self, start: usize) -> usize {
+        self.starts[start]
+    }
+
+    pub fn original_end(&self, end: usize) -> usize {
+        self.ends[end]
+    }
+
+    pub fn original_range(&self, start: usize, end: usize) -> (usize, usize) {
+        (self.original_start(start), self.original_end(end))
+    }
+}
@@ -0,0 +1,98 @@
+use crate::alignment::Alignment;
+
+/// Whether `byte` is an ASCII whitespace for `char::is_whitespace` (`\t`, `\n`, `\x0b`, `\x0c`,
+/// `\r` or space)
+fn is_ascii_whitespace(byte: u8) -> bool {
+    matches!(byte, b'\t'..=b'\r' | b' ')
+}
+
+/// Position of the first ASCII whitespace byte in `bytes`
+pub fn find_ascii_whitespace(bytes: &[u8]) -> Option<usize> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        find_ascii_whitespace_sse2(bytes)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        bytes.iter().position(|&byte| is_ascii_whitespace(byte))
+    }
+}
+
+/// Checks 16 bytes at a time, SSE2 is always available on x86_64
+#[cfg(target_arch = "x86_64")]
+fn find_ascii_whitespace_sse2(bytes: &[u8]) -> Option<usize> {
+    use std::arch::x86_64::{
+        __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_min_epu8, _mm_movemask_epi8, _mm_or_si128,
+        _mm_set1_epi8, _mm_sub_epi8,
+    };
+
+    const BLOCK_SIZE: usize = 16;
+    let mut position = 0;
+    // Safety: SSE2 is part of the x86_64 baseline and every load reads 16 bytes within `bytes`
+    unsafe {
+        let tab = _mm_set1_epi8(b'\t' as i8);
+        let control_range = _mm_set1_epi8((b'\r' - b'\t') as i8);
+        let space = _mm_set1_epi8(b' ' as i8);
+        while position + BLOCK_SIZE <= bytes.len() {
+            let block = _mm_loadu_si128(bytes.as_ptr().add(position) as *const __m128i);
+            // `byte - '\t' <= '\r' - '\t'` (wrapping) selects `\t`..=`\r`
+            let offset = _mm_sub_epi8(block, tab);
+            let is_control = _mm_cmpeq_epi8(_mm_min_epu8(offset, control_range), offset);
+            let is_whitespace = _mm_or_si128(is_control, _mm_cmpeq_epi8(block, space));
+            let mask = _mm_movemask_epi8(is_whitespace);
+            if mask != 0 {
+                return Some(position + mask.trailing_zeros() as usize);
+            }
+            position += BLOCK_SIZE;
+        }
+    }
+    bytes[position..]
+        .iter()
+        .position(|&byte| is_ascii_whitespace(byte))
+        .map(|offset| position + offset)
+}
+
+/// Fast path of `BpeTokenizer::pre_process_text` for ASCII text without normalizer: runs between
+/// whitespace are copied in bulk and every character maps to its own byte.
+pub fn pre_process_ascii(
+    input_text: &str,
+    whitespace_token: char,
+    add_dummy_whitespace: bool,
+    treat_whitespace_as_suffix: bool,
+    text: &mut String,
+    alignment: &mut Alignment,
+) {
+    text.clear();
+    alignment.clear();
+    text.reserve(input_text.len() + whitespace_token.len_utf8());
+    let has_whitespace_token = if treat_whitespace_as_suffix {
+        input_text.ends_with(whitespace_token)
+    } else {
+        input_text.starts_with(whitespace_token)
+    };
+    let add_dummy_whitespace = add_dummy_whitespace && !has_whitespace_token;
+
+    if add_dummy_whitespace && !treat_whitespace_as_suffix {
+        text.push(whitespace_token);
+        alignment.push(0, text.len(), 0, 0);
+    }
+    let bytes = input_text.as_bytes();
+    let mut position = 0;
+    while position < bytes.len() {
+        let run_end = find_ascii_whitespace(&bytes[position..])
+            .map_or(bytes.len(), |offset| position + offset);
+        alignment.push_ascii_run(text.len(), position, run_end - position);
+        text.push_str(&input_text[position..run_end]);
+        if run_end < bytes.len() {
+            let start = text.len();
+            text.push(whitespace_token);
+            alignment.push(start, text.len(), run_end, run_end + 1);
+        }
+        position = run_end + 1;
+    }
+    if add_dummy_whitespace && treat_whitespace_as_suffix {
+        let start = text.len();
+        text.push(whitespace_token);
+        alignment.push(start, text.len(), input_text.len(), input_text.len());
+    }
+}
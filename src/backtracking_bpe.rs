@@ -0,0 +1,219 @@
+use crate::bpe_base::{read_model_proto, BpeTokenizer, Symbol, TokenizerConfig};
+use crate::naive_bpe::SymbolArray;
+use crate::priority_queue_linked_list_bpe::PriorityQueueBpeLLTokenizer;
+use crate::workspace::Workspace;
+use protobuf::ProtobufError;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Byte trie of the vocabulary pieces that BPE can produce
+#[derive(Default)]
+pub struct VocabTrie {
+    children: HashMap<(usize, u8), usize>,
+    piece_ids: Vec<Option<i64>>,
+}
+
+impl VocabTrie {
+    pub fn new() -> Self {
+        Self {
+            children: HashMap::new(),
+            piece_ids: vec![None],
+        }
+    }
+
+    pub fn insert(&mut self, piece: &str, id: i64) {
+        let mut node = 0;
+        for byte in piece.bytes() {
+            node = match self.children.get(&(node, byte)) {
+                Some(&child) => child,
+                None => {
+                    self.piece_ids.push(None);
+                    let child = self.piece_ids.len() - 1;
+                    self.children.insert((node, byte), child);
+                    child
+                }
+            };
+        }
+        self.piece_ids[node] = Some(id);
+    }
+
+    /// Byte lengths and ids of the pieces that are a prefix of `text`, longest first
+    pub fn prefix_matches(&self, text: &str) -> Vec<(usize, i64)> {
+        let mut matches = Vec::new();
+        let mut node = 0;
+        for (index, byte) in text.bytes().enumerate() {
+            match self.children.get(&(node, byte)) {
+                Some(&child) => node = child,
+                None => break,
+            }
+            if let Some(id) = self.piece_ids[node] {
+                if text.is_char_boundary(index + 1) {
+                    matches.push((index + 1, id));
+                }
+            }
+        }
+        matches.reverse();
+        matches
+    }
+}
+
+struct BacktrackingFrame {
+    start: usize,
+    candidates: Vec<(usize, i64)>,
+    candidate_index: usize,
+}
+
+/// Linear-time BPE by backtracking over the possible token sequences.
+///
+/// The text is read left to right, trying the longest vocabulary piece first. A piece is kept if
+/// it is compatible with the previous one, i.e. BPE on the two pieces concatenated gives back
+/// these two pieces. Adjacent BPE tokens are always compatible and the sequence of pairwise
+/// compatible tokens covering a text is unique, so the first one found is the BPE output. When no
+/// piece fits at a position, the previous token is replaced by a shorter one and the position is
+/// marked as a dead end: every position is given up at most once, and the work at each position
+/// only depends on the longest piece length, making the encoding linear in the text length.
+pub struct BacktrackingBpeTokenizer {
+    config: TokenizerConfig,
+    vocab_trie: VocabTrie,
+}
+
+impl BacktrackingBpeTokenizer {
+    pub fn new(merges_path: &Path) -> Result<Self, ProtobufError> {
+        Self::from_model_proto(&read_model_proto(merges_path)?)
+    }
+
+    fn build(mut self) -> Self {
+        let mut vocab_trie = VocabTrie::new();
+        let mut workspace = Workspace::new();
+        let mut symbols = Vec::new();
+        for (piece, &id) in self.config.merges_vocab.iter() {
+            // Pieces BPE does not merge back into a single symbol can never be produced. Pieces
+            // can be long, so the priority queue algorithm is used rather than the quadratic one.
+            symbols.clear();
+            PriorityQueueBpeLLTokenizer::merge_into(
+                &self.config.merge_table,
+                piece,
+                &mut workspace,
+                &mut symbols,
+                &mut (),
+                None,
+            );
+            if symbols.len() == 1 {
+                vocab_trie.insert(piece, id);
+            }
+        }
+        self.vocab_trie = vocab_trie;
+        self
+    }
+
+    /// Runs BPE on a text of at most two pieces, for which the quadratic algorithm is fastest
+    fn merge_short_text(&self, text: &str) -> Vec<Symbol> {
+        let mut symbols = SymbolArray::from_text(text, &self.config.merge_table);
+        while let Some((best_pair_index, _, merged_id)) = symbols.find_best_merge(self) {
+            symbols.merge_symbols(best_pair_index, merged_id);
+        }
+        symbols.symbols
+    }
+
+    fn is_compatible(&self, text: &str, start: usize, middle: usize, end: usize) -> bool {
+        let symbols = self.merge_short_text(&text[start..end]);
+        symbols.len() == 2 && symbols[1].start_byte == middle - start
+    }
+
+    /// Candidate token lengths and ids at `position`: vocabulary pieces, longest first, and the
+    /// single character (unknown characters are kept as single symbols by BPE)
+    fn candidates(&self, text: &str, position: usize) -> Vec<(usize, i64)> {
+        let mut candidates = self.vocab_trie.prefix_matches(&text[position..]);
+        let character = text[position..].chars().next().unwrap();
+        if candidates.last().map(|&(length, _)| length) != Some(character.len_utf8()) {
+            candidates.push((
+                character.len_utf8(),
+                self.config.merge_table.char_id(character),
+            ));
+        }
+        candidates
+    }
+}
+
+impl BpeTokenizer for BacktrackingBpeTokenizer {
+    fn from_config(config: TokenizerConfig) -> Self {
+        Self {
+            config,
+            vocab_trie: VocabTrie::new(),
+        }
+        .build()
+    }
+
+    fn config(&self) -> &TokenizerConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut TokenizerConfig {
+        &mut self.config
+    }
+
+    fn apply_merges(&self, text: &str) -> Vec<Symbol> {
+        // `live_positions[position]` is false once no encoding can go through `position`
+        let mut live_positions = vec![true; text.len() + 1];
+        let mut stack: Vec<BacktrackingFrame> = Vec::new();
+        let mut position = 0;
+        let mut candidates = if text.is_empty() {
+            Vec::new()
+        } else {
+            self.candidates(text, 0)
+        };
+        let mut candidate_index = 0;
+
+        while position < text.len() {
+            let next_position = candidates[candidate_index..]
+                .iter()
+                .position(|&(length, _)| {
+                    let end = position + length;
+                    live_positions[end]
+                        && stack.last().map_or(true, |previous| {
+                            self.is_compatible(text, previous.start, position, end)
+                        })
+                })
+                .map(|offset| candidate_index + offset);
+
+            match next_position {
+                Some(index) => {
+                    let end = position + candidates[index].0;
+                    stack.push(BacktrackingFrame {
+                        start: position,
+                        candidates,
+                        candidate_index: index,
+                    });
+                    position = end;
+                    candidates = if end < text.len() {
+                        self.candidates(text, end)
+                    } else {
+                        Vec::new()
+                    };
+                    candidate_index = 0;
+                }
+                None => {
+                    live_positions[position] = false;
+                    let previous = stack
+                        .pop()
+                        .expect("the BPE segmentation is always a valid token sequence");
+                    position = previous.start;
+                    candidates = previous.candidates;
+                    candidate_index = previous.candidate_index + 1;
+                }
+            }
+        }
+
+        stack
+            .into_iter()
+            .map(|frame| {
+                let (length, id) = frame.candidates[frame.candidate_index];
+                Symbol {
+                    start_byte: frame.start,
+                    end_byte: frame.start + length,
+                    id,
+                }
+            })
+            .collect()
+    }
+}
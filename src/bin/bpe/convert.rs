@@ -0,0 +1,677 @@
+use anyhow::{anyhow, bail, Context};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use bpe_example::{
+    BpeTokenizer, ModelProto, ModelProto_SentencePiece, ModelProto_SentencePiece_Type as PieceType,
+    PreTokenizer, PriorityQueueBpeLLTokenizer, RegexPreTokenizer, TrainerSpec_ModelType,
+};
+use protobuf::Message;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+const UNK_PIECE: &str = "<unk>";
+
+/// Split pattern of GPT-2, on the text before its bytes are mapped to characters
+const GPT2_BYTE_LEVEL_PATTERN: &str =
+    r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+";
+
+/// Differing lines printed by `compare`
+const MAX_REPORTED_DIFFERENCES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFormat {
+    /// SentencePiece `.model`, which the tokenizers of the crate load
+    SentencePiece,
+    /// Hugging Face `tokenizer.json` with a BPE model
+    HuggingFace,
+    /// Directory with GPT-2 `vocab.json` and `merges.txt`
+    Gpt2,
+}
+
+impl ModelFormat {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "sentencepiece" => Self::SentencePiece,
+            "hf" => Self::HuggingFace,
+            "gpt2" => Self::Gpt2,
+            _ => unreachable!("unknown model format {}", name),
+        }
+    }
+}
+
+/// A model read from any format, as the pieces of a SentencePiece model. Merges-based formats
+/// also keep their ranked merges, which are applied as listed instead of being derived from the
+/// piece ids as in the tokenizers of the crate.
+pub struct ConvertedModel {
+    pub model_proto: ModelProto,
+    pub merges: Option<Vec<(String, String)>>,
+    /// Whether the pieces are byte-level as in GPT-2: every byte of the text stands for a
+    /// character, such as `Ġ` for a space, and there is no `▁` pre-processing
+    pub byte_level: bool,
+    /// Settings of the source that the model does not represent
+    pub warnings: Vec<String>,
+}
+
+/// Merges applied by the tokenizers of the crate: every split of a piece into two pieces, ranked
+/// by the id of the merged piece
+fn derive_merges(model_proto: &ModelProto) -> Vec<(String, String)> {
+    let vocabulary: HashSet<&str> = model_proto
+        .get_pieces()
+        .iter()
+        .map(|piece| piece.get_piece())
+        .collect();
+    let mut merges = Vec::new();
+    for piece in model_proto.get_pieces() {
+        let piece = piece.get_piece();
+        for (split, _) in piece.char_indices().skip(1) {
+            let (left, right) = piece.split_at(split);
+            if vocabulary.contains(left) && vocabulary.contains(right) {
+                merges.push((left.to_owned(), right.to_owned()));
+            }
+        }
+    }
+    merges
+}
+
+/// Merges to write to a merges-based format: those of the source model if it has any
+fn ranked_merges(model: &ConvertedModel) -> Vec<(String, String)> {
+    match &model.merges {
+        Some(merges) => merges.clone(),
+        None => derive_merges(&model.model_proto),
+    }
+}
+
+/// Characters standing for the bytes in byte-level pieces: printable Latin-1 characters stand
+/// for themselves, the other bytes for the characters from U+0100 on, in byte order
+fn byte_level_chars() -> [char; 256] {
+    let mut chars = ['\0'; 256];
+    let mut next_char = 0x100;
+    for (byte, byte_char) in chars.iter_mut().enumerate() {
+        *byte_char = match byte as u8 {
+            b'!'..=b'~' | 0xa1..=0xac | 0xae..=0xff => byte as u8 as char,
+            _ => {
+                next_char += 1;
+                char::from_u32(next_char - 1).unwrap()
+            }
+        };
+    }
+    chars
+}
+
+fn is_byte_piece(piece: &str) -> bool {
+    piece.len() == 6
+        && piece.starts_with("<0x")
+        && piece.ends_with('>')
+        && u8::from_str_radix(&piece[3..5], 16).is_ok()
+}
+
+/// Builds a BPE model from the pieces of a merges-based format, in id order with their type
+fn model_from_pieces(
+    pieces: Vec<(String, PieceType)>,
+    add_dummy_prefix: bool,
+    byte_fallback: bool,
+) -> ModelProto {
+    let mut model_proto = ModelProto::new();
+    let trainer_spec = model_proto.mut_trainer_spec();
+    trainer_spec.set_model_type(TrainerSpec_ModelType::BPE);
+    trainer_spec.set_vocab_size(pieces.len() as i32);
+    trainer_spec.set_byte_fallback(byte_fallback);
+    trainer_spec.set_bos_id(-1);
+    trainer_spec.set_eos_id(-1);
+    for (id, (piece, piece_type)) in pieces.iter().enumerate() {
+        match (piece.as_str(), piece_type) {
+            (_, PieceType::UNKNOWN) => {
+                trainer_spec.set_unk_id(id as i32);
+                trainer_spec.set_unk_piece(piece.clone());
+            }
+            ("<s>", PieceType::CONTROL) => trainer_spec.set_bos_id(id as i32),
+            ("</s>", PieceType::CONTROL) => trainer_spec.set_eos_id(id as i32),
+            ("<pad>", PieceType::CONTROL) => trainer_spec.set_pad_id(id as i32),
+            _ => {}
+        }
+    }
+    model_proto
+        .mut_normalizer_spec()
+        .set_add_dummy_prefix(add_dummy_prefix);
+    for (id, (piece, piece_type)) in pieces.into_iter().enumerate() {
+        let mut sentence_piece = ModelProto_SentencePiece::new();
+        sentence_piece.set_score(if piece_type == PieceType::NORMAL {
+            -(id as f32)
+        } else {
+            0.0
+        });
+        sentence_piece.set_piece(piece);
+        sentence_piece.set_field_type(piece_type);
+        model_proto.mut_pieces().push(sentence_piece);
+    }
+    model_proto
+}
+
+/// Orders the pieces of a `{piece: id}` vocabulary by id, failing if the ids are not `0..n`
+fn pieces_by_id(vocabulary: HashMap<String, usize>) -> anyhow::Result<Vec<Option<String>>> {
+    let mut pieces = vec![None; vocabulary.len()];
+    for (piece, id) in vocabulary {
+        match pieces.get_mut(id) {
+            Some(slot @ None) => *slot = Some(piece),
+            Some(Some(other_piece)) => bail!("{:?} and {:?} have id {}", other_piece, piece, id),
+            None => bail!("id {} of {:?} is not below the vocabulary size", id, piece),
+        }
+    }
+    Ok(pieces)
+}
+
+/// Parses a merge written as `"left right"` or `["left", "right"]`
+fn parse_merge(merge: &Value) -> anyhow::Result<(String, String)> {
+    let pair = match merge {
+        Value::String(merge) => merge
+            .split_once(' ')
+            .map(|(left, right)| (left.to_owned(), right.to_owned())),
+        Value::Array(pair) => match pair.as_slice() {
+            [Value::String(left), Value::String(right)] => Some((left.clone(), right.clone())),
+            _ => None,
+        },
+        _ => None,
+    };
+    pair.ok_or_else(|| anyhow!("invalid merge {}", merge))
+}
+
+/// Whether a Hugging Face Metaspace pre-tokenizer or decoder adds a dummy prefix
+fn metaspace_adds_prefix(metaspace: &Value) -> bool {
+    match metaspace.get("prepend_scheme").and_then(Value::as_str) {
+        Some(prepend_scheme) => prepend_scheme != "never",
+        None => metaspace
+            .get("add_prefix_space")
+            .and_then(Value::as_bool)
+            .unwrap_or(true),
+    }
+}
+
+fn read_hugging_face(path: &Path) -> anyhow::Result<ConvertedModel> {
+    let tokenizer: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let model = &tokenizer["model"];
+    if model["type"] != "BPE" {
+        bail!("model type is {}, not BPE", model["type"]);
+    }
+    let mut warnings = Vec::new();
+
+    let vocabulary: HashMap<String, usize> =
+        serde_json::from_value(model["vocab"].clone()).context("invalid vocab")?;
+    let mut pieces = pieces_by_id(vocabulary)?;
+    let mut piece_types = HashMap::new();
+    for added_token in tokenizer["added_tokens"].as_array().unwrap_or(&Vec::new()) {
+        let content = added_token["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("invalid added token {}", added_token))?;
+        let id = added_token["id"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("invalid added token {}", added_token))?
+            as usize;
+        if id >= pieces.len() {
+            pieces.resize(id + 1, None);
+        }
+        match &pieces[id] {
+            Some(piece) if piece != content => {
+                bail!("added token {:?} has the id {} of {:?}", content, id, piece)
+            }
+            _ => pieces[id] = Some(content.to_owned()),
+        }
+        let piece_type = if added_token["special"] == true {
+            PieceType::CONTROL
+        } else {
+            PieceType::USER_DEFINED
+        };
+        piece_types.insert(content.to_owned(), piece_type);
+    }
+    if let Some(unk_token) = model["unk_token"].as_str() {
+        piece_types.insert(unk_token.to_owned(), PieceType::UNKNOWN);
+    }
+    let byte_fallback = model["byte_fallback"] == true;
+
+    let mut add_dummy_prefix = false;
+    let mut precompiled_charsmap = Vec::new();
+    let normalizers = match &tokenizer["normalizer"] {
+        Value::Null => Vec::new(),
+        normalizer if normalizer["type"] == "Sequence" => normalizer["normalizers"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default(),
+        normalizer => vec![normalizer.clone()],
+    };
+    for normalizer in normalizers.iter() {
+        match normalizer["type"].as_str() {
+            Some("Precompiled") => {
+                precompiled_charsmap = BASE64
+                    .decode(normalizer["precompiled_charsmap"].as_str().unwrap_or(""))
+                    .context("invalid precompiled_charsmap")?
+            }
+            Some("Prepend") if normalizer["prepend"] == "\u{2581}" => add_dummy_prefix = true,
+            Some("Replace") if normalizer["content"] == "\u{2581}" => {}
+            _ => warnings.push(format!("normalizer {} is not converted", normalizer)),
+        }
+    }
+    match &tokenizer["pre_tokenizer"] {
+        Value::Null => {}
+        pre_tokenizer if pre_tokenizer["type"] == "Metaspace" => {
+            add_dummy_prefix = metaspace_adds_prefix(pre_tokenizer)
+        }
+        pre_tokenizer => warnings.push(format!("pre-tokenizer {} is not converted", pre_tokenizer)),
+    }
+
+    let pieces = pieces
+        .into_iter()
+        .enumerate()
+        .map(|(id, piece)| {
+            let piece = piece.ok_or_else(|| anyhow!("no piece has id {}", id))?;
+            let piece_type = match piece_types.get(&piece) {
+                Some(&piece_type) => piece_type,
+                None if byte_fallback && is_byte_piece(&piece) => PieceType::BYTE,
+                None => PieceType::NORMAL,
+            };
+            Ok((piece, piece_type))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let mut model_proto = model_from_pieces(pieces, add_dummy_prefix, byte_fallback);
+    if !precompiled_charsmap.is_empty() {
+        model_proto
+            .mut_normalizer_spec()
+            .set_precompiled_charsmap(precompiled_charsmap);
+    }
+    let merges = model["merges"]
+        .as_array()
+        .ok_or_else(|| anyhow!("no merges"))?
+        .iter()
+        .map(parse_merge)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(ConvertedModel {
+        model_proto,
+        merges: Some(merges),
+        byte_level: false,
+        warnings,
+    })
+}
+
+/// Reads GPT-2 files. They do not record piece types: pieces longer than a character that no
+/// merge produces are taken as control pieces, `<unk>` as the unknown piece. The pieces are
+/// taken as byte-level unless some contain `▁`, as those written from SentencePiece models do.
+fn read_gpt2(directory: &Path) -> anyhow::Result<ConvertedModel> {
+    let vocabulary_path = directory.join("vocab.json");
+    let vocabulary: HashMap<String, usize> =
+        serde_json::from_str(&fs::read_to_string(&vocabulary_path)?)
+            .with_context(|| format!("invalid {}", vocabulary_path.display()))?;
+    let merges_path = directory.join("merges.txt");
+    let merges = fs::read_to_string(&merges_path)?
+        .lines()
+        .filter(|line| !line.starts_with("#version") && !line.is_empty())
+        .map(|line| parse_merge(&Value::from(line)))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .with_context(|| format!("invalid {}", merges_path.display()))?;
+
+    let merged: HashSet<String> = merges
+        .iter()
+        .map(|(left, right)| format!("{}{}", left, right))
+        .collect();
+    let pieces = pieces_by_id(vocabulary)?
+        .into_iter()
+        .map(|piece| {
+            let piece = piece.unwrap();
+            let piece_type = if piece == UNK_PIECE {
+                PieceType::UNKNOWN
+            } else if piece.chars().nth(1).is_some() && !merged.contains(&piece) {
+                PieceType::CONTROL
+            } else {
+                PieceType::NORMAL
+            };
+            (piece, piece_type)
+        })
+        .collect::<Vec<_>>();
+    let byte_level = !pieces.iter().any(|(piece, _)| piece.contains('\u{2581}'));
+    let mut warnings = Vec::new();
+    if byte_level {
+        warnings.push(
+            "the pieces are byte-level and are not converted to `\u{2581}`-based pieces".to_owned(),
+        );
+    }
+    Ok(ConvertedModel {
+        model_proto: model_from_pieces(pieces, !byte_level, false),
+        merges: Some(merges),
+        byte_level,
+        warnings,
+    })
+}
+
+pub fn read_model(path: &Path, format: ModelFormat) -> anyhow::Result<ConvertedModel> {
+    match format {
+        ModelFormat::SentencePiece => Ok(ConvertedModel {
+            model_proto: bpe_example::read_model_proto(path)?,
+            merges: None,
+            byte_level: false,
+            warnings: Vec::new(),
+        }),
+        ModelFormat::HuggingFace => read_hugging_face(path),
+        ModelFormat::Gpt2 => read_gpt2(path),
+    }
+    .with_context(|| format!("cannot read {}", path.display()))
+}
+
+/// Warnings for the normalization of a model that merges-based formats cannot represent
+fn normalization_warnings(model_proto: &ModelProto, supports_charsmap: bool) -> Vec<String> {
+    let normalizer_spec = model_proto.get_normalizer_spec();
+    let mut warnings = Vec::new();
+    if !supports_charsmap && !normalizer_spec.get_precompiled_charsmap().is_empty() {
+        warnings.push("the precompiled charsmap is not converted".to_owned());
+    }
+    if model_proto
+        .get_trainer_spec()
+        .get_treat_whitespace_as_suffix()
+    {
+        warnings.push("treat_whitespace_as_suffix is not converted".to_owned());
+    }
+    warnings
+}
+
+fn write_hugging_face(model: &ConvertedModel, path: &Path) -> anyhow::Result<Vec<String>> {
+    let model_proto = &model.model_proto;
+    let pieces = model_proto.get_pieces();
+    let mut vocabulary = Map::new();
+    let mut added_tokens = Vec::new();
+    for (id, piece) in pieces.iter().enumerate() {
+        vocabulary.insert(piece.get_piece().to_owned(), json!(id));
+        let piece_type = piece.get_field_type();
+        if let PieceType::UNKNOWN | PieceType::CONTROL | PieceType::USER_DEFINED = piece_type {
+            let is_user_defined = piece_type == PieceType::USER_DEFINED;
+            added_tokens.push(json!({
+                "id": id,
+                "content": piece.get_piece(),
+                "single_word": false,
+                "lstrip": false,
+                "rstrip": false,
+                "normalized": is_user_defined,
+                "special": !is_user_defined,
+            }));
+        }
+    }
+    let unk_token = pieces
+        .iter()
+        .find(|piece| piece.get_field_type() == PieceType::UNKNOWN)
+        .map(|piece| piece.get_piece());
+    let merges: Vec<String> = ranked_merges(model)
+        .into_iter()
+        .map(|(left, right)| format!("{} {}", left, right))
+        .collect();
+
+    let normalizer_spec = model_proto.get_normalizer_spec();
+    let precompiled_charsmap = normalizer_spec.get_precompiled_charsmap();
+    let normalizer = if precompiled_charsmap.is_empty() {
+        Value::Null
+    } else {
+        json!({
+            "type": "Precompiled",
+            "precompiled_charsmap": BASE64.encode(precompiled_charsmap),
+        })
+    };
+    let metaspace = json!({
+        "type": "Metaspace",
+        "replacement": "\u{2581}",
+        "prepend_scheme": if normalizer_spec.get_add_dummy_prefix() { "always" } else { "never" },
+        "split": model_proto.get_trainer_spec().get_split_by_whitespace(),
+    });
+    let tokenizer = json!({
+        "version": "1.0",
+        "truncation": null,
+        "padding": null,
+        "added_tokens": added_tokens,
+        "normalizer": normalizer,
+        "pre_tokenizer": metaspace,
+        "post_processor": null,
+        "decoder": metaspace,
+        "model": {
+            "type": "BPE",
+            "dropout": null,
+            "unk_token": unk_token,
+            "continuing_subword_prefix": null,
+            "end_of_word_suffix": null,
+            "fuse_unk": false,
+            "byte_fallback": model_proto.get_trainer_spec().get_byte_fallback(),
+            "vocab": vocabulary,
+            "merges": merges,
+        },
+    });
+    fs::write(path, serde_json::to_string_pretty(&tokenizer)?)?;
+    Ok(normalization_warnings(model_proto, true))
+}
+
+fn write_gpt2(model: &ConvertedModel, directory: &Path) -> anyhow::Result<Vec<String>> {
+    let model_proto = &model.model_proto;
+    let vocabulary: Map<String, Value> = model_proto
+        .get_pieces()
+        .iter()
+        .enumerate()
+        .map(|(id, piece)| (piece.get_piece().to_owned(), json!(id)))
+        .collect();
+    let mut merges = String::from("#version: 0.2\n");
+    for (left, right) in ranked_merges(model) {
+        merges.push_str(&format!("{} {}\n", left, right));
+    }
+    fs::create_dir_all(directory)?;
+    fs::write(
+        directory.join("vocab.json"),
+        serde_json::to_string(&vocabulary)?,
+    )?;
+    fs::write(directory.join("merges.txt"), merges)?;
+
+    let mut warnings = normalization_warnings(model_proto, false);
+    if !model.byte_level {
+        warnings.push(
+            "the pieces use `\u{2581}` for spaces, GPT-2 tokenizers expect byte-level pieces"
+                .to_owned(),
+        );
+    }
+    if !model.byte_level && !model_proto.get_normalizer_spec().get_add_dummy_prefix() {
+        warnings.push("add_dummy_prefix is not converted".to_owned());
+    }
+    Ok(warnings)
+}
+
+/// Writes a model, returning warnings for the settings that the format cannot represent
+pub fn write_model(
+    model: &ConvertedModel,
+    path: &Path,
+    format: ModelFormat,
+) -> anyhow::Result<Vec<String>> {
+    match format {
+        ModelFormat::SentencePiece => {
+            fs::write(path, model.model_proto.write_to_bytes()?)?;
+            Ok(Vec::new())
+        }
+        ModelFormat::HuggingFace => write_hugging_face(model, path),
+        ModelFormat::Gpt2 => write_gpt2(model, path),
+    }
+    .with_context(|| format!("cannot write {}", path.display()))
+}
+
+/// BPE of merges-based formats: merges the adjacent pair with the lowest rank, leftmost first,
+/// until no listed merge applies. Merges of symbols or into pieces missing from the vocabulary
+/// never apply.
+struct RankedMerges {
+    /// Rank and merged id of the merges, keyed by the ids of their symbols
+    merges: HashMap<(i64, i64), (usize, i64)>,
+    ids: HashMap<String, i64>,
+    unk_id: i64,
+}
+
+impl RankedMerges {
+    fn new(model_proto: &ModelProto, merges: &[(String, String)]) -> Self {
+        let ids: HashMap<String, i64> = model_proto
+            .get_pieces()
+            .iter()
+            .enumerate()
+            .map(|(id, piece)| (piece.get_piece().to_owned(), id as i64))
+            .collect();
+        let mut ranked_merges = HashMap::new();
+        for (rank, (left, right)) in merges.iter().enumerate() {
+            let merged = format!("{}{}", left, right);
+            if let (Some(&left_id), Some(&right_id), Some(&merged_id)) =
+                (ids.get(left), ids.get(right), ids.get(&merged))
+            {
+                ranked_merges
+                    .entry((left_id, right_id))
+                    .or_insert((rank, merged_id));
+            }
+        }
+        let unk_id = model_proto
+            .get_pieces()
+            .iter()
+            .position(|piece| piece.get_field_type() == PieceType::UNKNOWN)
+            .unwrap_or(0) as i64;
+        Self {
+            merges: ranked_merges,
+            ids,
+            unk_id,
+        }
+    }
+
+    fn encode(&self, pre_processed_text: &str) -> Vec<i64> {
+        let mut symbols: Vec<i64> = pre_processed_text
+            .chars()
+            .map(|character| {
+                let mut buffer = [0; 4];
+                let character: &str = character.encode_utf8(&mut buffer);
+                self.ids.get(character).copied().unwrap_or(self.unk_id)
+            })
+            .collect();
+        let pair_merge = |symbols: &[i64], position: usize| {
+            self.merges
+                .get(&(symbols[position], symbols[position + 1]))
+                .copied()
+        };
+        // Rank and merged id of the merge of each symbol with the next one
+        let mut pair_merges: Vec<Option<(usize, i64)>> = (0..symbols.len().saturating_sub(1))
+            .map(|position| pair_merge(&symbols, position))
+            .collect();
+        while let Some((position, merged_id)) = pair_merges
+            .iter()
+            .enumerate()
+            .filter_map(|(position, merge)| {
+                merge.map(|(rank, merged_id)| (rank, position, merged_id))
+            })
+            .min()
+            .map(|(_, position, merged_id)| (position, merged_id))
+        {
+            symbols[position] = merged_id;
+            symbols.remove(position + 1);
+            pair_merges.remove(position);
+            if position < pair_merges.len() {
+                pair_merges[position] = pair_merge(&symbols, position);
+            }
+            if position > 0 {
+                pair_merges[position - 1] = pair_merge(&symbols, position - 1);
+            }
+        }
+        symbols
+    }
+}
+
+/// Tokenizes as the format of a model does: with the crate tokenizers for SentencePiece models,
+/// by ranked merges for merges-based formats, after the GPT-2 split and byte mapping for
+/// byte-level models
+struct ModelTokenizer<'a> {
+    model_proto: &'a ModelProto,
+    tokenizer: PriorityQueueBpeLLTokenizer,
+    ranked_merges: Option<RankedMerges>,
+    /// Split pattern and byte characters of byte-level models
+    byte_level: Option<(RegexPreTokenizer, [char; 256])>,
+}
+
+impl<'a> ModelTokenizer<'a> {
+    fn new(model: &'a ConvertedModel) -> anyhow::Result<Self> {
+        let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&model.model_proto)?;
+        Ok(Self {
+            model_proto: &model.model_proto,
+            tokenizer,
+            ranked_merges: model
+                .merges
+                .as_ref()
+                .map(|merges| RankedMerges::new(&model.model_proto, merges)),
+            byte_level: model.byte_level.then(|| {
+                (
+                    RegexPreTokenizer::new(GPT2_BYTE_LEVEL_PATTERN).unwrap(),
+                    byte_level_chars(),
+                )
+            }),
+        })
+    }
+
+    fn encode(&self, text: &str) -> Vec<i64> {
+        match (&self.ranked_merges, &self.byte_level) {
+            (None, _) => self.tokenizer.encode(text).ids,
+            (Some(ranked_merges), Some((pre_tokenizer, byte_chars))) => pre_tokenizer
+                .pre_tokenize(text)
+                .into_iter()
+                .flat_map(|word| {
+                    let word: String = word.bytes().map(|byte| byte_chars[byte as usize]).collect();
+                    ranked_merges.encode(&word)
+                })
+                .collect(),
+            (Some(ranked_merges), None) => {
+                let (pre_processed_text, _) = self.tokenizer.pre_process_text(
+                    text,
+                    '\u{2581}',
+                    self.tokenizer.add_dummy_prefix(),
+                );
+                ranked_merges.encode(&pre_processed_text)
+            }
+        }
+    }
+
+    fn pieces(&self, ids: &[i64]) -> String {
+        ids.iter()
+            .map(|&id| match self.model_proto.get_pieces().get(id as usize) {
+                Some(piece) => piece.get_piece(),
+                None => "?",
+            })
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
+/// Tokenizes every line of a corpus with both models and reports the lines with different ids,
+/// returning their number
+pub fn compare<R, W>(
+    source: &ConvertedModel,
+    target: &ConvertedModel,
+    corpus: R,
+    output: &mut W,
+) -> anyhow::Result<usize>
+where
+    R: BufRead,
+    W: Write,
+{
+    let source_tokenizer = ModelTokenizer::new(source)?;
+    let target_tokenizer = ModelTokenizer::new(target)?;
+    let mut n_lines = 0;
+    let mut n_differences = 0;
+    for (line_index, line) in corpus.lines().enumerate() {
+        let line = line?;
+        n_lines += 1;
+        let source_ids = source_tokenizer.encode(&line);
+        let target_ids = target_tokenizer.encode(&line);
+        if source_ids == target_ids {
+            continue;
+        }
+        n_differences += 1;
+        if n_differences <= MAX_REPORTED_DIFFERENCES {
+            writeln!(output, "line {}: {:?}", line_index + 1, line)?;
+            writeln!(output, "  source: {}", source_tokenizer.pieces(&source_ids))?;
+            writeln!(output, "  target: {}", target_tokenizer.pieces(&target_ids))?;
+        }
+    }
+    writeln!(
+        output,
+        "{} of {} lines tokenized differently",
+        n_differences, n_lines
+    )?;
+    Ok(n_differences)
+}
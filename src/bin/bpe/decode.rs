@@ -0,0 +1,38 @@
+use anyhow::{anyhow, Context};
+use bpe_example::Decoder;
+use serde_json::Value;
+use std::io::{BufRead, Write};
+
+/// Ids of a line of space-separated ids, a JSON array of ids or a JSON object with an `ids` field
+/// (as written by `bpe encode --format jsonl`)
+fn parse_ids(line: &str) -> anyhow::Result<Vec<i64>> {
+    let line = line.trim();
+    if line.starts_with('{') {
+        let mut record: Value = serde_json::from_str(line)?;
+        let ids = record
+            .get_mut("ids")
+            .ok_or_else(|| anyhow!("no \"ids\" field"))?
+            .take();
+        Ok(serde_json::from_value(ids)?)
+    } else if line.starts_with('[') {
+        Ok(serde_json::from_str(line)?)
+    } else {
+        line.split_whitespace()
+            .map(|id| id.parse().with_context(|| format!("invalid id {:?}", id)))
+            .collect()
+    }
+}
+
+/// Decodes every line on its own, writing each text as soon as the line is read
+pub fn decode_lines<R, W>(decoder: &Decoder, input: R, output: &mut W) -> anyhow::Result<()>
+where
+    R: BufRead,
+    W: Write,
+{
+    for (line_index, line) in input.lines().enumerate() {
+        let ids = parse_ids(&line?).with_context(|| format!("line {}", line_index + 1))?;
+        writeln!(output, "{}", decoder.decode(&ids))?;
+        output.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use bpe_example::{BpeTokenizer, Encoding};
+use serde_json::json;
+use std::io::{self, BufRead, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Pieces,
+    Ids,
+    Jsonl,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "pieces" => Self::Pieces,
+            "ids" => Self::Ids,
+            "jsonl" => Self::Jsonl,
+            _ => unreachable!("unknown output format {}", name),
+        }
+    }
+}
+
+/// Encoding of a text along with its pieces, from a single pass of the merge algorithm. Pieces
+/// are those of the pre-processed text, as in the vocabulary (unknown characters are kept as is).
+fn encode_with_pieces<T>(tokenizer: &T, text: &str) -> (Vec<String>, Encoding)
+where
+    T: BpeTokenizer,
+{
+    let (pre_processed_text, alignment) =
+        tokenizer.pre_process_text(text, '\u{2581}', tokenizer.add_dummy_prefix());
+    let mut pieces = Vec::new();
+    let mut encoding = Encoding::default();
+    for symbol in tokenizer.apply_merges(&pre_processed_text) {
+        pieces.push(pre_processed_text[symbol.byte_range()].to_owned());
+        encoding.ids.push(tokenizer.get_symbol_id(&symbol));
+        encoding
+            .offsets
+            .push(alignment.original_range(symbol.start_byte, symbol.end_byte));
+    }
+    (pieces, encoding)
+}
+
+/// Writes the encoding of a text as a single line
+fn write_encoding<T, W>(
+    tokenizer: &T,
+    text: &str,
+    format: OutputFormat,
+    output: &mut W,
+) -> io::Result<()>
+where
+    T: BpeTokenizer,
+    W: Write,
+{
+    let (pieces, encoding) = encode_with_pieces(tokenizer, text);
+    match format {
+        OutputFormat::Pieces => writeln!(output, "{}", pieces.join(" "))?,
+        OutputFormat::Ids => {
+            let ids: Vec<String> = encoding.ids.iter().map(i64::to_string).collect();
+            writeln!(output, "{}", ids.join(" "))?
+        }
+        OutputFormat::Jsonl => {
+            let record = json!({
+                "pieces": pieces,
+                "ids": encoding.ids,
+                "offsets": encoding.offsets,
+            });
+            writeln!(output, "{}", record)?
+        }
+    }
+    output.flush()
+}
+
+/// Encodes every line on its own, writing each encoding as soon as the line is read
+pub fn encode_lines<T, R, W>(
+    tokenizer: &T,
+    input: R,
+    format: OutputFormat,
+    output: &mut W,
+) -> io::Result<()>
+where
+    T: BpeTokenizer,
+    R: BufRead,
+    W: Write,
+{
+    for line in input.lines() {
+        write_encoding(tokenizer, &line?, format, output)?;
+    }
+    Ok(())
+}
+
+/// Encodes a whole input as a single text, without its final line break
+pub fn encode_whole<T, R, W>(
+    tokenizer: &T,
+    mut input: R,
+    format: OutputFormat,
+    output: &mut W,
+) -> io::Result<()>
+where
+    T: BpeTokenizer,
+    R: BufRead,
+    W: Write,
+{
+    let mut text = String::new();
+    input.read_to_string(&mut text)?;
+    if text.ends_with('\n') {
+        text.pop();
+        if text.ends_with('\r') {
+            text.pop();
+        }
+    }
+    write_encoding(tokenizer, &text, format, output)
+}
@@ -0,0 +1,399 @@
+use bpe_example::{
+    ModelProto, ModelProto_SentencePiece_Type as PieceType, NormalizerSpec, TrainerSpec,
+    TrainerSpec_ModelType,
+};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::NORMAL,
+    PieceType::UNKNOWN,
+    PieceType::CONTROL,
+    PieceType::USER_DEFINED,
+    PieceType::BYTE,
+    PieceType::UNUSED,
+];
+
+/// A setting of a spec: name, whether it is set in the model and value
+type Setting = (&'static str, bool, String);
+
+fn format_strings(strings: &[String]) -> String {
+    format!("{:?}", strings)
+}
+
+fn trainer_settings(spec: &TrainerSpec) -> Vec<Setting> {
+    vec![
+        (
+            "model_type",
+            spec.has_model_type(),
+            format!("{:?}", spec.get_model_type()),
+        ),
+        (
+            "vocab_size",
+            spec.has_vocab_size(),
+            spec.get_vocab_size().to_string(),
+        ),
+        (
+            "input",
+            !spec.get_input().is_empty(),
+            format_strings(spec.get_input()),
+        ),
+        (
+            "model_prefix",
+            spec.has_model_prefix(),
+            format!("{:?}", spec.get_model_prefix()),
+        ),
+        (
+            "character_coverage",
+            spec.has_character_coverage(),
+            spec.get_character_coverage().to_string(),
+        ),
+        (
+            "input_sentence_size",
+            spec.has_input_sentence_size(),
+            spec.get_input_sentence_size().to_string(),
+        ),
+        (
+            "max_sentencepiece_length",
+            spec.has_max_sentencepiece_length(),
+            spec.get_max_sentencepiece_length().to_string(),
+        ),
+        (
+            "max_sentence_length",
+            spec.has_max_sentence_length(),
+            spec.get_max_sentence_length().to_string(),
+        ),
+        (
+            "split_by_unicode_script",
+            spec.has_split_by_unicode_script(),
+            spec.get_split_by_unicode_script().to_string(),
+        ),
+        (
+            "split_by_number",
+            spec.has_split_by_number(),
+            spec.get_split_by_number().to_string(),
+        ),
+        (
+            "split_by_whitespace",
+            spec.has_split_by_whitespace(),
+            spec.get_split_by_whitespace().to_string(),
+        ),
+        (
+            "split_digits",
+            spec.has_split_digits(),
+            spec.get_split_digits().to_string(),
+        ),
+        (
+            "treat_whitespace_as_suffix",
+            spec.has_treat_whitespace_as_suffix(),
+            spec.get_treat_whitespace_as_suffix().to_string(),
+        ),
+        (
+            "allow_whitespace_only_pieces",
+            spec.has_allow_whitespace_only_pieces(),
+            spec.get_allow_whitespace_only_pieces().to_string(),
+        ),
+        (
+            "byte_fallback",
+            spec.has_byte_fallback(),
+            spec.get_byte_fallback().to_string(),
+        ),
+        (
+            "control_symbols",
+            !spec.get_control_symbols().is_empty(),
+            format_strings(spec.get_control_symbols()),
+        ),
+        (
+            "user_defined_symbols",
+            !spec.get_user_defined_symbols().is_empty(),
+            format_strings(spec.get_user_defined_symbols()),
+        ),
+        ("unk_id", spec.has_unk_id(), spec.get_unk_id().to_string()),
+        ("bos_id", spec.has_bos_id(), spec.get_bos_id().to_string()),
+        ("eos_id", spec.has_eos_id(), spec.get_eos_id().to_string()),
+        ("pad_id", spec.has_pad_id(), spec.get_pad_id().to_string()),
+        (
+            "unk_piece",
+            spec.has_unk_piece(),
+            format!("{:?}", spec.get_unk_piece()),
+        ),
+        (
+            "bos_piece",
+            spec.has_bos_piece(),
+            format!("{:?}", spec.get_bos_piece()),
+        ),
+        (
+            "eos_piece",
+            spec.has_eos_piece(),
+            format!("{:?}", spec.get_eos_piece()),
+        ),
+        (
+            "pad_piece",
+            spec.has_pad_piece(),
+            format!("{:?}", spec.get_pad_piece()),
+        ),
+        (
+            "unk_surface",
+            spec.has_unk_surface(),
+            format!("{:?}", spec.get_unk_surface()),
+        ),
+    ]
+}
+
+fn normalizer_settings(spec: &NormalizerSpec) -> Vec<Setting> {
+    vec![
+        ("name", spec.has_name(), format!("{:?}", spec.get_name())),
+        (
+            "precompiled_charsmap",
+            spec.has_precompiled_charsmap(),
+            format!("{} bytes", spec.get_precompiled_charsmap().len()),
+        ),
+        (
+            "add_dummy_prefix",
+            spec.has_add_dummy_prefix(),
+            spec.get_add_dummy_prefix().to_string(),
+        ),
+        (
+            "remove_extra_whitespaces",
+            spec.has_remove_extra_whitespaces(),
+            spec.get_remove_extra_whitespaces().to_string(),
+        ),
+        (
+            "escape_whitespaces",
+            spec.has_escape_whitespaces(),
+            spec.get_escape_whitespaces().to_string(),
+        ),
+        (
+            "normalization_rule_tsv",
+            spec.has_normalization_rule_tsv(),
+            format!("{:?}", spec.get_normalization_rule_tsv()),
+        ),
+    ]
+}
+
+/// Problems of a model that loading it does not report: they make tokenizers silently produce
+/// other ids than the original SentencePiece model
+pub fn find_anomalies(model_proto: &ModelProto) -> Vec<String> {
+    let pieces = model_proto.get_pieces();
+    let mut anomalies = Vec::new();
+
+    let mut ids_by_piece: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (id, piece) in pieces.iter().enumerate() {
+        if piece.get_piece().is_empty() {
+            anomalies.push(format!("piece {} is empty", id));
+        }
+        if !piece.get_score().is_finite() {
+            anomalies.push(format!("piece {} has score {}", id, piece.get_score()));
+        }
+        ids_by_piece.entry(piece.get_piece()).or_default().push(id);
+    }
+    let mut duplicates: Vec<(&str, Vec<usize>)> = ids_by_piece
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .collect();
+    duplicates.sort_by_key(|(_, ids)| ids[0]);
+    for (piece, ids) in duplicates {
+        anomalies.push(format!("piece {:?} appears at ids {:?}", piece, ids));
+    }
+
+    let count_of = |piece_type: PieceType| {
+        pieces
+            .iter()
+            .filter(|piece| piece.get_field_type() == piece_type)
+            .count()
+    };
+    match count_of(PieceType::UNKNOWN) {
+        1 => {}
+        0 => anomalies.push("no UNKNOWN piece".to_owned()),
+        n_unknown => anomalies.push(format!("{} UNKNOWN pieces", n_unknown)),
+    }
+
+    if !model_proto.has_trainer_spec() {
+        anomalies.push("no trainer spec".to_owned());
+        return anomalies;
+    }
+    let trainer_spec = model_proto.get_trainer_spec();
+    if trainer_spec.get_model_type() != TrainerSpec_ModelType::BPE {
+        anomalies.push(format!(
+            "model type is {:?}, not BPE",
+            trainer_spec.get_model_type()
+        ));
+    }
+    if trainer_spec.has_vocab_size() && trainer_spec.get_vocab_size() as usize != pieces.len() {
+        anomalies.push(format!(
+            "vocab_size is {} but the model has {} pieces",
+            trainer_spec.get_vocab_size(),
+            pieces.len()
+        ));
+    }
+    let special_ids = [
+        ("unk_id", trainer_spec.get_unk_id(), PieceType::UNKNOWN),
+        ("bos_id", trainer_spec.get_bos_id(), PieceType::CONTROL),
+        ("eos_id", trainer_spec.get_eos_id(), PieceType::CONTROL),
+        ("pad_id", trainer_spec.get_pad_id(), PieceType::CONTROL),
+    ];
+    for &(name, id, piece_type) in special_ids.iter() {
+        // Negative ids disable the special piece
+        if id < 0 {
+            continue;
+        }
+        match pieces.get(id as usize) {
+            None => anomalies.push(format!("{} {} is outside of the vocabulary", name, id)),
+            Some(piece) if piece.get_field_type() != piece_type => anomalies.push(format!(
+                "{} {} is {:?} of type {:?}, not {:?}",
+                name,
+                id,
+                piece.get_piece(),
+                piece.get_field_type(),
+                piece_type
+            )),
+            Some(_) => {}
+        }
+    }
+    for symbol in trainer_spec
+        .get_control_symbols()
+        .iter()
+        .chain(trainer_spec.get_user_defined_symbols())
+    {
+        if !pieces.iter().any(|piece| piece.get_piece() == symbol) {
+            anomalies.push(format!(
+                "symbol {:?} of the trainer spec is not a piece",
+                symbol
+            ));
+        }
+    }
+    let n_bytes = count_of(PieceType::BYTE);
+    if trainer_spec.get_byte_fallback() && n_bytes != 256 {
+        anomalies.push(format!("byte_fallback is set with {} BYTE pieces", n_bytes));
+    }
+    anomalies
+}
+
+fn write_settings<W>(output: &mut W, settings: Vec<Setting>) -> io::Result<()>
+where
+    W: Write,
+{
+    for (name, is_set, value) in settings {
+        let default = if is_set { "" } else { " (default)" };
+        writeln!(output, "  {:<30} {}{}", name, value, default)?;
+    }
+    Ok(())
+}
+
+fn write_value<W, V>(output: &mut W, name: &str, value: V) -> io::Result<()>
+where
+    W: Write,
+    V: Display,
+{
+    writeln!(output, "  {:<30} {}", name, value)
+}
+
+/// Score range of pieces, ignoring non-finite scores
+fn score_range<I>(scores: I) -> Option<(f32, f32)>
+where
+    I: Iterator<Item = f32>,
+{
+    scores
+        .filter(|score| score.is_finite())
+        .fold(None, |range, score| match range {
+            None => Some((score, score)),
+            Some((min, max)) => Some((min.min(score), max.max(score))),
+        })
+}
+
+/// Writes the contents of a model: pieces, specs, self-test data and anomalies
+pub fn write_report<W>(model_proto: &ModelProto, n_longest: usize, output: &mut W) -> io::Result<()>
+where
+    W: Write,
+{
+    let pieces = model_proto.get_pieces();
+
+    writeln!(output, "pieces")?;
+    write_value(output, "vocabulary size", pieces.len())?;
+    for &piece_type in PIECE_TYPES.iter() {
+        let count = pieces
+            .iter()
+            .filter(|piece| piece.get_field_type() == piece_type)
+            .count();
+        write_value(output, &format!("{:?}", piece_type), count)?;
+    }
+
+    writeln!(output, "\nscores")?;
+    let ranges = [
+        (
+            "all pieces",
+            score_range(pieces.iter().map(|piece| piece.get_score())),
+        ),
+        (
+            "NORMAL pieces",
+            score_range(
+                pieces
+                    .iter()
+                    .filter(|piece| piece.get_field_type() == PieceType::NORMAL)
+                    .map(|piece| piece.get_score()),
+            ),
+        ),
+    ];
+    for (name, range) in ranges.iter() {
+        match range {
+            Some((min, max)) => write_value(output, name, format!("{} to {}", min, max))?,
+            None => write_value(output, name, "none")?,
+        }
+    }
+
+    writeln!(output, "\nlongest pieces")?;
+    let mut longest: Vec<usize> = (0..pieces.len()).collect();
+    longest.sort_by_key(|&id| std::cmp::Reverse(pieces[id].get_piece().chars().count()));
+    for &id in longest.iter().take(n_longest) {
+        let piece = pieces[id].get_piece();
+        writeln!(
+            output,
+            "  {:>8}  {:>3} chars  {:?}",
+            id,
+            piece.chars().count(),
+            piece
+        )?;
+    }
+
+    writeln!(output, "\ntrainer spec")?;
+    if model_proto.has_trainer_spec() {
+        write_settings(output, trainer_settings(model_proto.get_trainer_spec()))?;
+    } else {
+        writeln!(output, "  none")?;
+    }
+    writeln!(output, "\nnormalizer spec")?;
+    if model_proto.has_normalizer_spec() {
+        write_settings(
+            output,
+            normalizer_settings(model_proto.get_normalizer_spec()),
+        )?;
+    } else {
+        writeln!(output, "  none")?;
+    }
+    if model_proto.has_denormalizer_spec() {
+        writeln!(output, "\ndenormalizer spec")?;
+        write_settings(
+            output,
+            normalizer_settings(model_proto.get_denormalizer_spec()),
+        )?;
+    }
+
+    writeln!(output, "\nself-test data")?;
+    let samples = model_proto.get_self_test_data().get_samples();
+    if samples.is_empty() {
+        writeln!(output, "  none")?;
+    } else {
+        write_value(output, "samples", samples.len())?;
+    }
+
+    writeln!(output, "\nanomalies")?;
+    let anomalies = find_anomalies(model_proto);
+    if anomalies.is_empty() {
+        writeln!(output, "  none")?;
+    }
+    for anomaly in anomalies {
+        writeln!(output, "  {}", anomaly)?;
+    }
+    Ok(())
+}
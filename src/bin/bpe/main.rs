@@ -0,0 +1,294 @@
+//! Command-line tokenizer: `bpe encode` and `bpe decode` read stdin or files and write one output
+//! line per input line (or per input with `encode --whole`), flushing each line as it is written.
+//! `bpe inspect` prints the contents of a model and `bpe convert` converts it to another format.
+mod convert;
+mod decode;
+mod encode;
+mod inspect;
+
+use anyhow::{bail, Context};
+use bpe_example::{
+    read_model_proto, BacktrackingBpeTokenizer, BpeTokenizer, Decoder, ModelProto,
+    NaiveBpeTokenizer, NaivePreSplitBpeTokenizer, PriorityQueueBpeLLTokenizer,
+    PriorityQueueBpeTokenizer,
+};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use convert::ModelFormat;
+use encode::OutputFormat;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::process;
+
+const MODEL_FORMATS: [&str; 3] = ["sentencepiece", "hf", "gpt2"];
+
+const ALGORITHMS: [&str; 5] = [
+    "naive",
+    "priority-queue",
+    "priority-queue-linked-list",
+    "backtracking",
+    "naive-pre-split",
+];
+
+fn model_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("model")
+        .long("model")
+        .short("m")
+        .value_name("PATH")
+        .env("BPE_MODEL_PATH")
+        .required(true)
+        .help("SentencePiece BPE model")
+}
+
+fn inputs_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("inputs")
+        .value_name("FILE")
+        .multiple(true)
+        .help("Input files, `-` or none for stdin")
+}
+
+fn load_model(matches: &ArgMatches) -> anyhow::Result<ModelProto> {
+    let model_path = matches.value_of("model").unwrap();
+    read_model_proto(Path::new(model_path))
+        .with_context(|| format!("cannot read model {}", model_path))
+}
+
+/// Input files of a subcommand, stdin if none is given
+fn input_names<'a>(matches: &'a ArgMatches) -> Vec<&'a str> {
+    match matches.values_of("inputs") {
+        Some(inputs) => inputs.collect(),
+        None => vec!["-"],
+    }
+}
+
+fn open_input(name: &str) -> anyhow::Result<Box<dyn BufRead>> {
+    Ok(if name == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(
+            File::open(name).with_context(|| format!("cannot open {}", name))?,
+        ))
+    })
+}
+
+fn encode_with<T>(tokenizer: &T, matches: &ArgMatches) -> anyhow::Result<()>
+where
+    T: BpeTokenizer,
+{
+    let format = OutputFormat::from_name(matches.value_of("format").unwrap());
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    for name in input_names(matches) {
+        let input = open_input(name)?;
+        if matches.is_present("whole") {
+            encode::encode_whole(tokenizer, input, format, &mut output)
+        } else {
+            encode::encode_lines(tokenizer, input, format, &mut output)
+        }
+        .with_context(|| format!("cannot encode {}", name))?;
+    }
+    Ok(())
+}
+
+fn encode(matches: &ArgMatches) -> anyhow::Result<()> {
+    let model_proto = load_model(matches)?;
+    match matches.value_of("algorithm").unwrap() {
+        "naive" => encode_with(&NaiveBpeTokenizer::from_model_proto(&model_proto)?, matches),
+        "priority-queue" => encode_with(
+            &PriorityQueueBpeTokenizer::from_model_proto(&model_proto)?,
+            matches,
+        ),
+        "priority-queue-linked-list" => encode_with(
+            &PriorityQueueBpeLLTokenizer::from_model_proto(&model_proto)?,
+            matches,
+        ),
+        "backtracking" => encode_with(
+            &BacktrackingBpeTokenizer::from_model_proto(&model_proto)?,
+            matches,
+        ),
+        "naive-pre-split" => encode_with(
+            &NaivePreSplitBpeTokenizer::from_model_proto(&model_proto)?,
+            matches,
+        ),
+        algorithm => unreachable!("unknown algorithm {}", algorithm),
+    }
+}
+
+fn decode(matches: &ArgMatches) -> anyhow::Result<()> {
+    let decoder = Decoder::from_model_proto(&load_model(matches)?)?;
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    for name in input_names(matches) {
+        decode::decode_lines(&decoder, open_input(name)?, &mut output)
+            .with_context(|| format!("cannot decode {}", name))?;
+    }
+    Ok(())
+}
+
+fn inspect(matches: &ArgMatches) -> anyhow::Result<()> {
+    let model_proto = load_model(matches)?;
+    let n_longest = matches
+        .value_of("longest")
+        .unwrap()
+        .parse()
+        .context("invalid --longest")?;
+    let stdout = io::stdout();
+    inspect::write_report(&model_proto, n_longest, &mut stdout.lock())?;
+    if matches.is_present("strict") && !inspect::find_anomalies(&model_proto).is_empty() {
+        bail!("{} has anomalies", matches.value_of("model").unwrap());
+    }
+    Ok(())
+}
+
+fn convert(matches: &ArgMatches) -> anyhow::Result<()> {
+    let source_path = Path::new(matches.value_of("source").unwrap());
+    let target_path = Path::new(matches.value_of("target").unwrap());
+    let target_format = ModelFormat::from_name(matches.value_of("to").unwrap());
+    let source = convert::read_model(
+        source_path,
+        ModelFormat::from_name(matches.value_of("from").unwrap()),
+    )?;
+    let write_warnings = convert::write_model(&source, target_path, target_format)?;
+    for warning in source.warnings.iter().chain(write_warnings.iter()) {
+        eprintln!("bpe: warning: {}", warning);
+    }
+
+    let target = convert::read_model(target_path, target_format)?;
+    let corpus = open_input(matches.value_of("corpus").unwrap())?;
+    let stdout = io::stdout();
+    let n_differences = convert::compare(&source, &target, corpus, &mut stdout.lock())?;
+    if n_differences > 0 {
+        bail!(
+            "{} tokenizes {} lines differently",
+            target_path.display(),
+            n_differences
+        );
+    }
+    Ok(())
+}
+
+fn run() -> anyhow::Result<()> {
+    let matches = App::new("bpe")
+        .about("Byte pair encoding with SentencePiece BPE models")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("encode")
+                .about("Encodes text into pieces or ids")
+                .arg(model_arg())
+                .arg(
+                    Arg::with_name("algorithm")
+                        .long("algorithm")
+                        .short("a")
+                        .possible_values(&ALGORITHMS)
+                        .default_value("priority-queue-linked-list")
+                        .help("BPE implementation"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .short("f")
+                        .possible_values(&["pieces", "ids", "jsonl"])
+                        .default_value("pieces")
+                        .help("Space-separated pieces or ids, or JSON objects with pieces, ids and offsets"),
+                )
+                .arg(
+                    Arg::with_name("whole")
+                        .long("whole")
+                        .help("Encodes each input as a single text instead of line by line"),
+                )
+                .arg(inputs_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("decode")
+                .about("Decodes lines of space-separated ids, JSON arrays or JSON objects with ids")
+                .arg(model_arg())
+                .arg(inputs_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("inspect")
+                .about("Prints the pieces, specs and self-test data of a model, and its anomalies")
+                .arg(
+                    Arg::with_name("model")
+                        .value_name("MODEL")
+                        .env("BPE_MODEL_PATH")
+                        .required(true)
+                        .help("SentencePiece model"),
+                )
+                .arg(
+                    Arg::with_name("longest")
+                        .long("longest")
+                        .value_name("N")
+                        .default_value("10")
+                        .help("Number of longest pieces to print"),
+                )
+                .arg(
+                    Arg::with_name("strict")
+                        .long("strict")
+                        .help("Exits with an error if the model has anomalies"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Converts a model to another format and compares their tokenization of a corpus")
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .value_name("FORMAT")
+                        .possible_values(&MODEL_FORMATS)
+                        .required(true)
+                        .help("Format of the source model"),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("FORMAT")
+                        .possible_values(&MODEL_FORMATS)
+                        .required(true)
+                        .help("Format of the target model"),
+                )
+                .arg(
+                    Arg::with_name("corpus")
+                        .long("corpus")
+                        .value_name("PATH")
+                        .env("BPE_CORPUS_PATH")
+                        .required(true)
+                        .help("Text tokenized with both models, `-` for stdin"),
+                )
+                .arg(
+                    Arg::with_name("source")
+                        .value_name("SOURCE")
+                        .required(true)
+                        .help("Model file, or directory with vocab.json and merges.txt for gpt2"),
+                )
+                .arg(
+                    Arg::with_name("target")
+                        .value_name("TARGET")
+                        .required(true)
+                        .help("Path of the converted model"),
+                ),
+        )
+        .get_matches();
+
+    match matches.subcommand() {
+        ("encode", Some(matches)) => encode(matches),
+        ("decode", Some(matches)) => decode(matches),
+        ("inspect", Some(matches)) => inspect(matches),
+        ("convert", Some(matches)) => convert(matches),
+        _ => unreachable!("a subcommand is required"),
+    }
+}
+
+fn main() {
+    if let Err(error) = run() {
+        // A closed output (e.g. piped into `head`) ends the command like a closed input does
+        let broken_pipe = error.chain().any(|cause| {
+            cause
+                .downcast_ref::<io::Error>()
+                .is_some_and(|io_error| io_error.kind() == io::ErrorKind::BrokenPipe)
+        });
+        if !broken_pipe {
+            eprintln!("bpe: {:#}", error);
+            process::exit(1);
+        }
+    }
+}
@@ -1,17 +1,34 @@
-use crate::proto::sentencepiece_model::ModelProto;
+use crate::alignment::Alignment;
+use crate::ascii::pre_process_ascii;
+use crate::encoding::Encoding;
+use crate::merge_table::MergeTable;
+use crate::normalizer::{NormalizedString, Normalizer, SequenceNormalizer};
+use crate::proto::sentencepiece_model::{ModelProto, ModelProto_SentencePiece_Type};
+use crate::workspace::Workspace;
 use protobuf::{Message, ProtobufError};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
+use std::ops::Range;
 use std::path::Path;
 
 pub type MergesVocab = HashMap<String, i64>;
 
+pub const UNK_PIECE: &str = "<unk>";
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Symbol {
     pub start_byte: usize,
     pub end_byte: usize,
+    /// Id of the symbol in the merge table (see `MergeTable`)
+    pub id: i64,
+}
+
+impl Symbol {
+    pub fn byte_range(&self) -> Range<usize> {
+        self.start_byte..self.end_byte
+    }
 }
 
 impl Ord for Symbol {
@@ -33,6 +50,7 @@ pub struct SymbolPair {
     pub left: Symbol,
     pub right: Symbol,
     pub score: i64,
+    pub merged_id: i64,
 }
 
 impl Ord for SymbolPair {
@@ -50,58 +68,360 @@ impl PartialOrd for SymbolPair {
     }
 }
 
+pub fn read_model_proto(model_path: &Path) -> Result<ModelProto, ProtobufError> {
+    let mut f = File::open(model_path)?;
+    let mut contents = Vec::new();
+    let _ = f.read_to_end(&mut contents)?;
+    parse_model_proto(contents.as_slice())
+}
+
+/// Parses the bytes of a model file, which may come from an untrusted source
+pub fn parse_model_proto(bytes: &[u8]) -> Result<ModelProto, ProtobufError> {
+    ModelProto::parse_from_bytes(bytes)
+}
+
+pub fn merges_vocab_from_proto(proto: &ModelProto) -> MergesVocab {
+    let mut values = MergesVocab::new();
+    for (idx, piece) in proto.get_pieces().iter().enumerate() {
+        values.insert(piece.get_piece().to_owned(), idx as i64);
+    }
+    values
+}
+
+/// Normalizer of a model, built from the precompiled charsmap of its normalizer spec
+pub(crate) fn normalizer_from_proto(
+    model_proto: &ModelProto,
+) -> Result<Option<Box<dyn Normalizer>>, ProtobufError> {
+    let normalizer = SequenceNormalizer::from_normalizer_spec(model_proto.get_normalizer_spec())?;
+    Ok(normalizer.map(|normalizer| Box::new(normalizer) as _))
+}
+
+/// Vocabulary and pre-processing settings shared by the BPE tokenizers
+pub struct TokenizerConfig {
+    pub(crate) merges_vocab: MergesVocab,
+    pub(crate) merge_table: MergeTable,
+    pub(crate) normalizer: Option<Box<dyn Normalizer>>,
+    pub(crate) treat_whitespace_as_suffix: bool,
+    pub(crate) add_dummy_prefix: bool,
+    pub(crate) unk_id: i64,
+}
+
+impl TokenizerConfig {
+    /// The unknown id is that of the `<unk>` piece, 0 if there is none
+    pub fn from_merges_vocab(merges_vocab: MergesVocab) -> Self {
+        Self {
+            unk_id: merges_vocab.get(UNK_PIECE).copied().unwrap_or(0),
+            merge_table: MergeTable::from_merges_vocab(&merges_vocab),
+            merges_vocab,
+            normalizer: None,
+            treat_whitespace_as_suffix: false,
+            add_dummy_prefix: true,
+        }
+    }
+
+    /// Vocabulary, normalizer and pre-processing settings of a model, failing if its precompiled
+    /// charsmap is invalid. The unknown id is that of the `UNKNOWN` piece, as in the decoder, or
+    /// the `unk_id` of the trainer specification if no piece has this type.
+    pub fn from_model_proto(model_proto: &ModelProto) -> Result<Self, ProtobufError> {
+        let unk_id = match model_proto
+            .get_pieces()
+            .iter()
+            .position(|piece| piece.get_field_type() == ModelProto_SentencePiece_Type::UNKNOWN)
+        {
+            Some(position) => position as i64,
+            None => model_proto.get_trainer_spec().get_unk_id() as i64,
+        };
+        Ok(Self {
+            unk_id,
+            normalizer: normalizer_from_proto(model_proto)?,
+            treat_whitespace_as_suffix: model_proto
+                .get_trainer_spec()
+                .get_treat_whitespace_as_suffix(),
+            add_dummy_prefix: model_proto.get_normalizer_spec().get_add_dummy_prefix(),
+            ..Self::from_merges_vocab(merges_vocab_from_proto(model_proto))
+        })
+    }
+}
+
 pub trait BpeTokenizer {
     fn read_proto(merges_path: &Path) -> Result<MergesVocab, ProtobufError> {
-        let mut f = File::open(merges_path)?;
-        let mut contents = Vec::new();
-        let _ = f.read_to_end(&mut contents)?;
-        let proto = ModelProto::parse_from_bytes(contents.as_slice())?;
-
-        let mut values = MergesVocab::new();
-        for (idx, piece) in proto.get_pieces().iter().enumerate() {
-            values.insert(piece.get_piece().to_owned(), idx as i64);
-        }
-        Ok(values)
+        Ok(merges_vocab_from_proto(&read_model_proto(merges_path)?))
     }
 
+    /// Replaces whitespace by the whitespace token and adds a dummy whitespace token at the
+    /// start of the text (at the end if whitespace is treated as suffix)
     fn pre_process_text(
         &self,
         input_text: &str,
         whitespace_token: char,
-    ) -> (String, HashMap<usize, usize>) {
-        let mut byte_mapping: HashMap<usize, usize> = HashMap::new();
-        let whitespace_token_len = whitespace_token.len_utf8();
-        let mut pre_processed_text = Vec::new();
-        let mut offset = 0;
-
-        if !input_text.starts_with(whitespace_token) {
-            pre_processed_text.push(whitespace_token);
-            byte_mapping.insert(0, 0);
-            offset += whitespace_token_len;
-        };
+        add_dummy_whitespace: bool,
+    ) -> (String, Alignment) {
+        let mut text = String::new();
+        let mut alignment = Alignment::default();
+        self.pre_process_text_into(
+            input_text,
+            whitespace_token,
+            add_dummy_whitespace,
+            &mut NormalizedString::default(),
+            &mut text,
+            &mut alignment,
+        );
+        (text, alignment)
+    }
 
-        for (character_start, character) in input_text.char_indices() {
-            byte_mapping.insert(character_start + offset, character_start);
+    /// Same as `pre_process_text`, writing into existing buffers. ASCII text without normalizer
+    /// takes a vectorized fast path producing the same text and alignment.
+    fn pre_process_text_into(
+        &self,
+        input_text: &str,
+        whitespace_token: char,
+        add_dummy_whitespace: bool,
+        normalized: &mut NormalizedString,
+        text: &mut String,
+        alignment: &mut Alignment,
+    ) {
+        if self.get_normalizer().is_none() && input_text.is_ascii() {
+            pre_process_ascii(
+                input_text,
+                whitespace_token,
+                add_dummy_whitespace,
+                self.treat_whitespace_as_suffix(),
+                text,
+                alignment,
+            );
+        } else {
+            self.pre_process_into(
+                input_text,
+                whitespace_token,
+                add_dummy_whitespace,
+                normalized,
+            );
+            normalized.write_text_and_alignment(text, alignment);
+        }
+    }
+
+    /// Pre-processing through a `NormalizedString`, reusing its buffer
+    fn pre_process_into(
+        &self,
+        input_text: &str,
+        whitespace_token: char,
+        add_dummy_whitespace: bool,
+        normalized: &mut NormalizedString,
+    ) {
+        normalized.reset(input_text);
+        if let Some(normalizer) = self.get_normalizer() {
+            normalizer.normalize(normalized);
+        }
+
+        let has_whitespace_token = if self.treat_whitespace_as_suffix() {
+            normalized.chars.last()
+        } else {
+            normalized.chars.first()
+        }
+        .is_some_and(|c| c.character == whitespace_token);
+        normalized.map(|character| {
             if character.is_whitespace() {
-                pre_processed_text.push(whitespace_token);
-                offset += whitespace_token_len - 1;
+                whitespace_token
+            } else {
+                character
+            }
+        });
+        if add_dummy_whitespace && !has_whitespace_token {
+            if self.treat_whitespace_as_suffix() {
+                normalized.append(whitespace_token);
             } else {
-                pre_processed_text.push(character);
+                normalized.prepend(whitespace_token);
             }
+        };
+    }
+
+    /// Builds the tokenizer from its vocabulary and settings, used by every other constructor
+    fn from_config(config: TokenizerConfig) -> Self
+    where
+        Self: Sized;
+
+    /// Vocabulary and settings, which the getters and `with_*` builders read and update
+    fn config(&self) -> &TokenizerConfig;
+
+    fn config_mut(&mut self) -> &mut TokenizerConfig;
+
+    fn from_merges_vocab(merges_vocab: MergesVocab) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_config(TokenizerConfig::from_merges_vocab(merges_vocab))
+    }
+
+    /// Builds the tokenizer with the vocabulary, normalizer and pre-processing settings of a
+    /// model, failing if its precompiled charsmap is invalid
+    fn from_model_proto(model_proto: &ModelProto) -> Result<Self, ProtobufError>
+    where
+        Self: Sized,
+    {
+        Ok(Self::from_config(TokenizerConfig::from_model_proto(
+            model_proto,
+        )?))
+    }
+
+    fn with_normalizer<N>(mut self, normalizer: N) -> Self
+    where
+        Self: Sized,
+        N: Normalizer + 'static,
+    {
+        self.config_mut().normalizer = Some(Box::new(normalizer));
+        self
+    }
+
+    fn with_whitespace_as_suffix(mut self, treat_whitespace_as_suffix: bool) -> Self
+    where
+        Self: Sized,
+    {
+        self.config_mut().treat_whitespace_as_suffix = treat_whitespace_as_suffix;
+        self
+    }
+
+    fn with_dummy_prefix(mut self, add_dummy_prefix: bool) -> Self
+    where
+        Self: Sized,
+    {
+        self.config_mut().add_dummy_prefix = add_dummy_prefix;
+        self
+    }
+
+    fn get_merges_vocab(&self) -> &MergesVocab {
+        &self.config().merges_vocab
+    }
+
+    fn get_merge_table(&self) -> &MergeTable {
+        &self.config().merge_table
+    }
+
+    fn get_normalizer(&self) -> Option<&dyn Normalizer> {
+        self.config().normalizer.as_deref()
+    }
+
+    /// Whether the whitespace token ends words (`word▁`) instead of starting them (`▁word`)
+    fn treat_whitespace_as_suffix(&self) -> bool {
+        self.config().treat_whitespace_as_suffix
+    }
+
+    /// Whether a dummy whitespace token is added to the text, as words are always prefixed (or
+    /// suffixed) by whitespace in training
+    fn add_dummy_prefix(&self) -> bool {
+        self.config().add_dummy_prefix
+    }
+
+    /// Returns the score of merging two symbols and the id of the merged symbol
+    fn get_merge(&self, symbol_1: &Symbol, symbol_2: &Symbol) -> Option<(i64, i64)> {
+        self.get_merge_table().get_merge(symbol_1.id, symbol_2.id)
+    }
+
+    /// Vocabulary id of a symbol, the unknown id for characters that are not in the vocabulary
+    fn get_symbol_id(&self, symbol: &Symbol) -> i64 {
+        if self.get_merge_table().is_piece_id(symbol.id) {
+            symbol.id
+        } else {
+            self.get_unk_id()
         }
-        let pre_processed_text = pre_processed_text.iter().collect::<String>();
-        byte_mapping.insert(pre_processed_text.len(), input_text.len());
+    }
+
+    /// Whether text can be cut before any whitespace (after it if whitespace is treated as
+    /// suffix) without changing the tokenization, i.e. the normalizer keeps whitespace and no
+    /// merge can produce a symbol containing the whitespace token past its first character
+    /// (before its last character)
+    fn can_split_on_whitespace(&self, whitespace_token: char) -> bool {
+        let treat_whitespace_as_suffix = self.treat_whitespace_as_suffix();
+        self.get_normalizer()
+            .map_or(true, |normalizer| normalizer.keeps_whitespace())
+            && !self.get_merges_vocab().keys().any(|piece| {
+                let mut characters: Vec<char> = piece.chars().collect();
+                if treat_whitespace_as_suffix {
+                    characters.pop();
+                } else if !characters.is_empty() {
+                    characters.remove(0);
+                }
+                characters.contains(&whitespace_token)
+            })
+    }
 
-        (pre_processed_text, byte_mapping)
+    fn get_unk_id(&self) -> i64 {
+        self.config().unk_id
     }
 
-    fn get_merges_vocab(&self) -> &MergesVocab;
+    /// Runs the merge algorithm on a pre-processed text, returning the final symbols
+    fn apply_merges(&self, text: &str) -> Vec<Symbol>;
 
-    fn get_merge_score(&self, symbol_1: &Symbol, symbol_2: &Symbol, text: &str) -> Option<i64> {
-        self.get_merges_vocab()
-            .get(&text[symbol_1.start_byte..symbol_2.end_byte])
-            .copied()
+    /// Same as `apply_merges`, appending the symbols to `symbols`. Implementations can keep their
+    /// scratch buffers in the workspace to avoid allocating.
+    fn apply_merges_into(&self, text: &str, _workspace: &mut Workspace, symbols: &mut Vec<Symbol>) {
+        symbols.extend(self.apply_merges(text));
     }
 
-    fn tokenize<'a>(&self, input_text: &'a str) -> Vec<&'a str>;
+    fn tokenize<'a>(&self, input_text: &'a str) -> Vec<&'a str> {
+        self.tokenize_chunk(input_text, self.add_dummy_prefix())
+    }
+
+    /// Tokenizes a piece of a larger text, the dummy whitespace token is only added to the
+    /// first chunk of a text (last chunk if whitespace is treated as suffix)
+    fn tokenize_chunk<'a>(&self, input_text: &'a str, add_dummy_whitespace: bool) -> Vec<&'a str> {
+        let (text, alignment) = self.pre_process_text(input_text, '\u{2581}', add_dummy_whitespace);
+
+        let mut output = Vec::new();
+        for symbol in self.apply_merges(text.as_str()) {
+            let (start, end) = alignment.original_range(symbol.start_byte, symbol.end_byte);
+            output.push(&input_text[start..end]);
+        }
+        output
+    }
+
+    fn encode(&self, input_text: &str) -> Encoding {
+        let (text, alignment) =
+            self.pre_process_text(input_text, '\u{2581}', self.add_dummy_prefix());
+
+        let mut encoding = Encoding::default();
+        for symbol in self.apply_merges(text.as_str()) {
+            encoding.ids.push(self.get_symbol_id(&symbol));
+            encoding
+                .offsets
+                .push(alignment.original_range(symbol.start_byte, symbol.end_byte));
+        }
+        encoding
+    }
+
+    /// Same as `encode`, overwriting `output` and keeping all intermediate buffers in `workspace`.
+    /// Once the buffers have grown to the size of the inputs, encoding does not allocate unless a
+    /// normalizer or the merge algorithm does (see `apply_merges_into`).
+    fn encode_into(&self, workspace: &mut Workspace, input_text: &str, output: &mut Encoding) {
+        output.ids.clear();
+        output.offsets.clear();
+        self.pre_process_text_into(
+            input_text,
+            '\u{2581}',
+            self.add_dummy_prefix(),
+            &mut workspace.normalized,
+            &mut workspace.text,
+            &mut workspace.alignment,
+        );
+
+        // Taken out of the workspace while the merge algorithm borrows it
+        let text = std::mem::take(&mut workspace.text);
+        let mut symbols = std::mem::take(&mut workspace.symbols);
+        symbols.clear();
+        self.apply_merges_into(text.as_str(), workspace, &mut symbols);
+        for symbol in symbols.iter() {
+            output.ids.push(self.get_symbol_id(symbol));
+            output.offsets.push(
+                workspace
+                    .alignment
+                    .original_range(symbol.start_byte, symbol.end_byte),
+            );
+        }
+        workspace.text = text;
+        workspace.symbols = symbols;
+    }
+
+    /// Encodes the text once and splits the result in overlapping windows (see `Encoding::windows`)
+    fn encode_windows(&self, input_text: &str, max_length: usize, stride: usize) -> Vec<Encoding> {
+        self.encode(input_text).windows(max_length, stride)
+    }
 }
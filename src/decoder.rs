@@ -0,0 +1,91 @@
+use crate::bpe_base::read_model_proto;
+use crate::normalizer::{NormalizedString, Normalizer, SequenceNormalizer};
+use crate::proto::sentencepiece_model::{ModelProto, ModelProto_SentencePiece_Type};
+use protobuf::ProtobufError;
+use std::path::Path;
+
+/// Turns ids back into text: pieces are joined, whitespace tokens replaced by spaces and the
+/// model denormalizer (`ModelProto.denormalizer_spec`) applied to the result.
+pub struct Decoder {
+    pieces: Vec<String>,
+    piece_types: Vec<ModelProto_SentencePiece_Type>,
+    unk_surface: String,
+    add_dummy_prefix: bool,
+    treat_whitespace_as_suffix: bool,
+    denormalizer: Option<Box<dyn Normalizer>>,
+}
+
+impl Decoder {
+    pub fn new(model_path: &Path) -> Result<Self, ProtobufError> {
+        Self::from_model_proto(&read_model_proto(model_path)?)
+    }
+
+    pub fn from_model_proto(model_proto: &ModelProto) -> Result<Self, ProtobufError> {
+        let denormalizer = if model_proto.has_denormalizer_spec() {
+            SequenceNormalizer::from_normalizer_spec(model_proto.get_denormalizer_spec())?
+        } else {
+            None
+        };
+        Ok(Self {
+            pieces: model_proto
+                .get_pieces()
+                .iter()
+                .map(|piece| piece.get_piece().to_owned())
+                .collect(),
+            piece_types: model_proto
+                .get_pieces()
+                .iter()
+                .map(|piece| piece.get_field_type())
+                .collect(),
+            unk_surface: model_proto.get_trainer_spec().get_unk_surface().to_owned(),
+            add_dummy_prefix: model_proto.get_normalizer_spec().get_add_dummy_prefix(),
+            treat_whitespace_as_suffix: model_proto
+                .get_trainer_spec()
+                .get_treat_whitespace_as_suffix(),
+            denormalizer: denormalizer.map(|denormalizer| Box::new(denormalizer) as _),
+        })
+    }
+
+    pub fn with_denormalizer<N>(mut self, denormalizer: N) -> Self
+    where
+        N: Normalizer + 'static,
+    {
+        self.denormalizer = Some(Box::new(denormalizer));
+        self
+    }
+
+    pub fn decode(&self, ids: &[i64]) -> String {
+        let mut text = String::new();
+        for &id in ids {
+            let piece_index = id as usize;
+            match self.piece_types.get(piece_index) {
+                Some(ModelProto_SentencePiece_Type::CONTROL) => {}
+                Some(ModelProto_SentencePiece_Type::UNKNOWN) | None => {
+                    text.push_str(self.unk_surface.as_str())
+                }
+                Some(_) => text.push_str(self.pieces[piece_index].as_str()),
+            }
+        }
+        // The dummy whitespace token added during pre-processing is dropped
+        if self.add_dummy_prefix {
+            let stripped = if self.treat_whitespace_as_suffix {
+                text.strip_suffix('\u{2581}')
+            } else {
+                text.strip_prefix('\u{2581}')
+            };
+            if let Some(stripped) = stripped {
+                text = stripped.to_owned();
+            }
+        }
+        let text = text.replace('\u{2581}', " ");
+
+        match &self.denormalizer {
+            Some(denormalizer) => {
+                let mut denormalized = NormalizedString::from_text(text.as_str());
+                denormalizer.normalize(&mut denormalized);
+                denormalized.text()
+            }
+            None => text,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+/// Token ids and their byte offsets in the original (un-processed) input text
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Encoding {
+    pub ids: Vec<i64>,
+    pub offsets: Vec<(usize, usize)>,
+}
+
+impl Encoding {
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns the tokens in `start..end` as a new encoding, offsets still refer to the original text
+    pub fn slice(&self, start: usize, end: usize) -> Encoding {
+        Encoding {
+            ids: self.ids[start..end].to_vec(),
+            offsets: self.offsets[start..end].to_vec(),
+        }
+    }
+
+    /// Splits the encoding in windows of at most `max_length` tokens, consecutive windows sharing
+    /// `stride` tokens. An encoding that fits in `max_length` is returned as a single window.
+    pub fn windows(&self, max_length: usize, stride: usize) -> Vec<Encoding> {
+        assert!(
+            stride < max_length,
+            "stride ({}) must be smaller than max_length ({})",
+            stride,
+            max_length
+        );
+        let step = max_length - stride;
+        let mut windows = Vec::new();
+        let mut start = 0;
+        loop {
+            let end = (start + max_length).min(self.len());
+            windows.push(self.slice(start, end));
+            if end == self.len() {
+                break;
+            }
+            start += step;
+        }
+        windows
+    }
+}
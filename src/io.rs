@@ -6,6 +6,7 @@ use std::io::BufRead;
 use std::path::{Path, PathBuf};
 
 /// Download a file target to a cache location
+#[allow(clippy::result_large_err)]
 pub fn download_file_to_cache(src: &str, target: &str) -> Result<PathBuf, ureq::Error> {
     let mut home = dirs::home_dir().unwrap();
     home.push(".cache");
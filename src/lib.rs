@@ -1,14 +1,60 @@
+mod alignment;
+mod ascii;
+mod backtracking_bpe;
 mod bpe_base;
+mod decoder;
+mod encoding;
 mod io;
+mod merge_table;
+mod merge_trace;
 mod naive_bpe;
-mod naive_pre_split_bpe;
+mod normalizer;
+mod parallel;
+mod pre_split_bpe;
+mod pre_tokenizer;
+mod precompiled_normalizer;
 mod priority_queue_bpe;
 mod priority_queue_linked_list_bpe;
+mod profiling;
 mod proto;
+mod rule_normalizer;
+mod streaming;
+mod word_cache;
+mod workspace;
 
-pub use bpe_base::BpeTokenizer;
+pub use alignment::Alignment;
+pub use backtracking_bpe::BacktrackingBpeTokenizer;
+pub use bpe_base::{parse_model_proto, read_model_proto, BpeTokenizer, TokenizerConfig};
+pub use decoder::Decoder;
+pub use encoding::Encoding;
 pub use io::{download_file_to_cache, get_file_header};
+pub use merge_table::{MergeTable, UNKNOWN_SYMBOL_ID};
+pub use merge_trace::{MergeStep, MergeTrace};
 pub use naive_bpe::NaiveBpeTokenizer;
-pub use naive_pre_split_bpe::NaivePreSplitBpeTokenizer;
+pub use normalizer::{
+    AlignedChar, LowercaseNormalizer, NormalizationForm, NormalizedString, Normalizer,
+    ReplaceNormalizer, SequenceNormalizer, StripAccentsNormalizer, StripControlNormalizer,
+    UnicodeNormalizer,
+};
+pub use parallel::ParallelTokenizer;
+pub use pre_split_bpe::{
+    NaivePreSplitBpeTokenizer, PreSplitBpeTokenizer, PriorityQueuePreSplitBpeLLTokenizer,
+    PriorityQueuePreSplitBpeTokenizer,
+};
+pub use pre_tokenizer::{
+    split_whitespace_punctuation, DigitsPreTokenizer, NumbersPreTokenizer, PreTokenizer,
+    PunctuationPreTokenizer, RegexPreTokenizer, SequencePreTokenizer, UnicodeScriptPreTokenizer,
+    WhitespacePreTokenizer, WhitespacePunctuationPreTokenizer, CL100K_PATTERN, GPT2_PATTERN,
+};
+pub use precompiled_normalizer::PrecompiledNormalizer;
 pub use priority_queue_bpe::PriorityQueueBpeTokenizer;
 pub use priority_queue_linked_list_bpe::PriorityQueueBpeLLTokenizer;
+pub use profiling::AgendaStats;
+pub use proto::sentencepiece_model::{
+    ModelProto, ModelProto_SentencePiece, ModelProto_SentencePiece_Type, NormalizerSpec,
+    TrainerSpec, TrainerSpec_ModelType,
+};
+pub use rule_normalizer::RuleNormalizer;
+pub use streaming::StreamingTokenizer;
+pub use word_cache::{CacheStats, WordCache};
+pub use workspace::Workspace;
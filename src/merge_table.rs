@@ -0,0 +1,152 @@
+use crate::bpe_base::{MergesVocab, Symbol};
+use rustc_hash::FxHashMap;
+
+/// Id of characters that are not part of any piece and can therefore never be merged
+pub const UNKNOWN_SYMBOL_ID: i64 = -1;
+
+/// Merges keyed by the ids of the two symbols, as in GPT-2 merges tables, hashed with FxHash.
+///
+/// Any two symbols whose concatenation is a piece of the vocabulary are merged, with the id of
+/// the piece as rank. Symbols are either pieces (with their vocabulary id) or single characters:
+/// characters only found inside longer pieces get ids above the vocabulary ids so that merges
+/// starting from them can be looked up as well.
+#[derive(Debug, Clone, Default)]
+pub struct MergeTable {
+    char_ids: FxHashMap<char, i64>,
+    merges: FxHashMap<(i64, i64), (i64, i64)>,
+    max_piece_id: i64,
+}
+
+impl MergeTable {
+    pub fn from_merges_vocab(merges_vocab: &MergesVocab) -> Self {
+        let max_piece_id = merges_vocab.values().copied().max().unwrap_or(-1);
+        let mut char_ids = FxHashMap::default();
+        let mut next_char_id = max_piece_id + 1;
+        for piece in merges_vocab.keys() {
+            for character in piece.chars() {
+                char_ids.entry(character).or_insert_with(|| {
+                    let mut buffer = [0; 4];
+                    match merges_vocab.get(character.encode_utf8(&mut buffer) as &str) {
+                        Some(&id) => id,
+                        None => {
+                            next_char_id += 1;
+                            next_char_id - 1
+                        }
+                    }
+                });
+            }
+        }
+
+        let mut table = Self {
+            char_ids,
+            merges: FxHashMap::default(),
+            max_piece_id,
+        };
+        // The pieces prefixing and suffixing each piece are found by walking tries of the pieces
+        // and of the reversed pieces, in time linear in the piece lengths
+        let mut prefixes = ByteTrie::default();
+        let mut suffixes = ByteTrie::default();
+        for (piece, &id) in merges_vocab.iter() {
+            prefixes.insert(piece.bytes(), id);
+            suffixes.insert(piece.bytes().rev(), id);
+        }
+        for (piece, &id) in merges_vocab.iter() {
+            let prefix_ids = prefixes.matches(piece.bytes());
+            let suffix_ids = suffixes.matches(piece.bytes().rev());
+            let mut characters = piece.char_indices().peekable();
+            while let Some((character_start, character)) = characters.next() {
+                let split = match characters.peek() {
+                    Some(&(split, _)) => split,
+                    None => break,
+                };
+                // Single characters are symbols even when they are not pieces
+                let left_id = prefix_ids[split]
+                    .or_else(|| (character_start == 0).then(|| table.char_id(character)));
+                let right_id = suffix_ids[piece.len() - split].or_else(|| {
+                    let mut right_characters = piece[split..].chars();
+                    match (right_characters.next(), right_characters.next()) {
+                        (Some(right_character), None) => Some(table.char_id(right_character)),
+                        _ => None,
+                    }
+                });
+                if let (Some(left_id), Some(right_id)) = (left_id, right_id) {
+                    table.merges.insert((left_id, right_id), (id, id));
+                }
+            }
+        }
+        table
+    }
+
+    pub fn char_id(&self, character: char) -> i64 {
+        self.char_ids
+            .get(&character)
+            .copied()
+            .unwrap_or(UNKNOWN_SYMBOL_ID)
+    }
+
+    /// Whether the id of a symbol is a vocabulary id (and not a character-only id)
+    pub fn is_piece_id(&self, id: i64) -> bool {
+        0 <= id && id <= self.max_piece_id
+    }
+
+    /// Returns the rank of the merge and the id of the merged symbol
+    pub fn get_merge(&self, left_id: i64, right_id: i64) -> Option<(i64, i64)> {
+        self.merges.get(&(left_id, right_id)).copied()
+    }
+
+    /// Single-character symbols of a text
+    pub fn char_symbols<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Symbol> + 'a {
+        text.char_indices()
+            .map(move |(character_start, character)| Symbol {
+                start_byte: character_start,
+                end_byte: character_start + character.len_utf8(),
+                id: self.char_id(character),
+            })
+    }
+}
+
+/// Byte trie of pieces, read forwards or backwards
+#[derive(Default)]
+struct ByteTrie {
+    children: FxHashMap<(usize, u8), usize>,
+    piece_ids: Vec<Option<i64>>,
+}
+
+impl ByteTrie {
+    fn insert<I>(&mut self, bytes: I, id: i64)
+    where
+        I: Iterator<Item = u8>,
+    {
+        if self.piece_ids.is_empty() {
+            self.piece_ids.push(None);
+        }
+        let mut node = 0;
+        for byte in bytes {
+            let next_node = self.piece_ids.len();
+            node = *self.children.entry((node, byte)).or_insert(next_node);
+            if node == next_node {
+                self.piece_ids.push(None);
+            }
+        }
+        self.piece_ids[node] = Some(id);
+    }
+
+    /// Ids of the pieces starting `bytes`, indexed by their length in bytes
+    fn matches<I>(&self, bytes: I) -> Vec<Option<i64>>
+    where
+        I: Iterator<Item = u8>,
+    {
+        let mut matches = vec![None];
+        let mut node = 0;
+        for byte in bytes {
+            match self.children.get(&(node, byte)) {
+                Some(&child) => {
+                    node = child;
+                    matches.push(self.piece_ids[node]);
+                }
+                None => break,
+            }
+        }
+        matches
+    }
+}
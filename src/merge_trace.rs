@@ -0,0 +1,123 @@
+use std::fmt;
+use std::ops::Range;
+
+/// A merge of two adjacent symbols into one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeStep {
+    /// Left and right symbols of the merged pair
+    pub pair: (String, String),
+    /// Rank of the merge, lower ranks being merged first
+    pub rank: i64,
+    /// Index of the left symbol of the pair in the symbols before the merge
+    pub position: usize,
+    /// Byte range of the merged symbol in the pre-processed text
+    pub byte_range: Range<usize>,
+    /// Symbols after the merge
+    pub symbols: Vec<String>,
+}
+
+/// Merges made by BPE on a text, in order, as returned by the `explain` methods of the tokenizers.
+///
+/// Printing a trace shows one row per merge with the symbols after it, the first row holding the
+/// characters of the pre-processed text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeTrace {
+    /// Pre-processed text
+    pub text: String,
+    pub steps: Vec<MergeStep>,
+}
+
+impl MergeTrace {
+    pub(crate) fn new(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Characters of the pre-processed text, the symbols BPE starts from
+    pub fn initial_symbols(&self) -> Vec<String> {
+        self.text.chars().map(String::from).collect()
+    }
+
+    /// Symbols after the last merge
+    pub fn final_symbols(&self) -> Vec<String> {
+        match self.steps.last() {
+            Some(step) => step.symbols.clone(),
+            None => self.initial_symbols(),
+        }
+    }
+
+    /// Records the merge of `left` and `right`, given the byte ranges of the symbols after it
+    pub(crate) fn record_merge<I>(
+        &mut self,
+        left: Range<usize>,
+        right: Range<usize>,
+        rank: i64,
+        symbols: I,
+    ) where
+        I: IntoIterator<Item = Range<usize>>,
+    {
+        let symbols: Vec<Range<usize>> = symbols.into_iter().collect();
+        self.steps.push(MergeStep {
+            pair: (
+                self.text[left.clone()].to_owned(),
+                self.text[right.clone()].to_owned(),
+            ),
+            rank,
+            position: symbols
+                .iter()
+                .take_while(|symbol| symbol.start < left.start)
+                .count(),
+            byte_range: left.start..right.end,
+            symbols: symbols
+                .into_iter()
+                .map(|symbol| self.text[symbol].to_owned())
+                .collect(),
+        });
+    }
+}
+
+fn format_symbols(symbols: &[String]) -> String {
+    symbols
+        .iter()
+        .map(|symbol| symbol.escape_debug().to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl fmt::Display for MergeTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>5} {:>8} {:>8}  {:<24} symbols",
+            "step", "rank", "position", "pair"
+        )?;
+        writeln!(
+            f,
+            "{:>5} {:>8} {:>8}  {:<24} {}",
+            0,
+            "",
+            "",
+            "",
+            format_symbols(&self.initial_symbols())
+        )?;
+        for (step_index, step) in self.steps.iter().enumerate() {
+            let pair = format!(
+                "{} + {}",
+                step.pair.0.escape_debug(),
+                step.pair.1.escape_debug()
+            );
+            writeln!(
+                f,
+                "{:>5} {:>8} {:>8}  {:<24} {}",
+                step_index + 1,
+                step.rank,
+                step.position,
+                pair,
+                format_symbols(&step.symbols)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
-use crate::bpe_base::{BpeTokenizer, MergesVocab, Symbol};
+use crate::bpe_base::{read_model_proto, BpeTokenizer, Symbol, TokenizerConfig};
+use crate::merge_table::MergeTable;
+use crate::merge_trace::MergeTrace;
 use itertools::Itertools;
 use protobuf::ProtobufError;
 use std::path::Path;
@@ -17,18 +19,14 @@ impl IntoIterator for SymbolArray {
 }
 
 impl SymbolArray {
-    pub fn from_text(input_text: &str) -> Self {
-        let mut symbols = Vec::new();
-        for (character_start, character) in input_text.char_indices() {
-            symbols.push(Symbol {
-                start_byte: character_start,
-                end_byte: character_start + character.len_utf8(),
-            });
+    pub fn from_text(input_text: &str, merge_table: &MergeTable) -> Self {
+        Self {
+            symbols: merge_table.char_symbols(input_text).collect(),
         }
-        Self { symbols }
     }
 
-    pub fn find_best_merge<T>(&self, input_text: &str, tokenizer: &T) -> Option<usize>
+    /// Returns the position of the best pair, its rank and the id of the merged symbol
+    pub fn find_best_merge<T>(&self, tokenizer: &T) -> Option<(usize, i64, i64)>
     where
         T: BpeTokenizer,
     {
@@ -38,17 +36,17 @@ impl SymbolArray {
             .enumerate()
             .filter_map(|(pos, (first, second))| {
                 tokenizer
-                    .get_merge_score(first, second, input_text)
-                    .map(|rank| (pos, rank))
+                    .get_merge(first, second)
+                    .map(|(rank, merged_id)| (pos, rank, merged_id))
             })
-            .min_by_key(|(_, rank)| *rank)
-            .map(|(pos, _)| pos)
+            .min_by_key(|(_, rank, _)| *rank)
     }
 
-    pub fn merge_symbols(&mut self, best_pair_index: usize) -> Symbol {
+    pub fn merge_symbols(&mut self, best_pair_index: usize, merged_id: i64) -> Symbol {
         let new_symbol = Symbol {
             start_byte: self.symbols[best_pair_index].start_byte,
             end_byte: self.symbols[best_pair_index + 1].end_byte,
+            id: merged_id,
         };
         self.symbols.remove(best_pair_index + 1);
         self.symbols.remove(best_pair_index);
@@ -58,34 +56,55 @@ impl SymbolArray {
 }
 
 pub struct NaiveBpeTokenizer {
-    merges_vocab: MergesVocab,
+    config: TokenizerConfig,
 }
 
 impl NaiveBpeTokenizer {
     pub fn new(merges_path: &Path) -> Result<Self, ProtobufError> {
-        let merges_vocab = Self::read_proto(merges_path)?;
-        Ok(Self { merges_vocab })
+        Self::from_model_proto(&read_model_proto(merges_path)?)
+    }
+
+    /// Records the merges made when tokenizing a text
+    pub fn explain(&self, input_text: &str) -> MergeTrace {
+        let (text, _) = self.pre_process_text(input_text, '\u{2581}', self.config.add_dummy_prefix);
+        let mut merge_trace = MergeTrace::new(&text);
+        self.merge(&text, Some(&mut merge_trace));
+        merge_trace
+    }
+
+    fn merge(&self, text: &str, mut merge_trace: Option<&mut MergeTrace>) -> Vec<Symbol> {
+        let mut symbols = SymbolArray::from_text(text, &self.config.merge_table);
+        while let Some((best_pair_index, rank, merged_id)) = symbols.find_best_merge(self) {
+            let left_symbol = symbols.symbols[best_pair_index];
+            let right_symbol = symbols.symbols[best_pair_index + 1];
+            symbols.merge_symbols(best_pair_index, merged_id);
+            if let Some(merge_trace) = merge_trace.as_deref_mut() {
+                merge_trace.record_merge(
+                    left_symbol.byte_range(),
+                    right_symbol.byte_range(),
+                    rank,
+                    symbols.symbols.iter().map(Symbol::byte_range),
+                );
+            }
+        }
+        symbols.symbols
     }
 }
 
 impl BpeTokenizer for NaiveBpeTokenizer {
-    fn get_merges_vocab(&self) -> &MergesVocab {
-        &self.merges_vocab
+    fn from_config(config: TokenizerConfig) -> Self {
+        Self { config }
     }
 
-    fn tokenize<'a>(&self, input_text: &'a str) -> Vec<&'a str> {
-        let (text, byte_mapping) = self.pre_process_text(input_text, '\u{2581}');
+    fn config(&self) -> &TokenizerConfig {
+        &self.config
+    }
 
-        let mut symbols = SymbolArray::from_text(text.as_str());
-        while let Some(best_pair_index) = symbols.find_best_merge(text.as_str(), self) {
-            symbols.merge_symbols(best_pair_index);
-        }
-        let mut output = Vec::new();
-        for symbol in symbols {
-            output.push(
-                &input_text[byte_mapping[&symbol.start_byte]..byte_mapping[&symbol.end_byte]],
-            );
-        }
-        output
+    fn config_mut(&mut self) -> &mut TokenizerConfig {
+        &mut self.config
+    }
+
+    fn apply_merges(&self, text: &str) -> Vec<Symbol> {
+        self.merge(text, None)
     }
 }
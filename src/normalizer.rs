@@ -0,0 +1,370 @@
+use crate::alignment::Alignment;
+use crate::precompiled_normalizer::PrecompiledNormalizer;
+use crate::proto::sentencepiece_model::NormalizerSpec;
+use fancy_regex::Regex;
+use std::io;
+use unicode_normalization::char::{canonical_combining_class, is_combining_mark};
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+/// Character of a normalized text with the byte range of the original text it comes from
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AlignedChar {
+    pub character: char,
+    pub original_start: usize,
+    pub original_end: usize,
+}
+
+/// Text being normalized, every character keeps track of its origin in the original text
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct NormalizedString {
+    pub chars: Vec<AlignedChar>,
+}
+
+impl NormalizedString {
+    pub fn from_text(text: &str) -> Self {
+        let mut normalized = Self::default();
+        normalized.reset(text);
+        normalized
+    }
+
+    /// Replaces the content by `text`, reusing the allocated buffer
+    pub fn reset(&mut self, text: &str) {
+        self.chars.clear();
+        self.chars.extend(
+            text.char_indices()
+                .map(|(character_start, character)| AlignedChar {
+                    character,
+                    original_start: character_start,
+                    original_end: character_start + character.len_utf8(),
+                }),
+        );
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().map(|c| c.character).collect()
+    }
+
+    pub fn map<F>(&mut self, mut f: F)
+    where
+        F: FnMut(char) -> char,
+    {
+        for c in self.chars.iter_mut() {
+            c.character = f(c.character);
+        }
+    }
+
+    /// Replaces every character by zero or more characters, all aligned to the original character
+    pub fn flat_map<F, I>(&mut self, mut f: F)
+    where
+        F: FnMut(char) -> I,
+        I: IntoIterator<Item = char>,
+    {
+        let mut output = Vec::with_capacity(self.chars.len());
+        for c in self.chars.iter() {
+            output.extend(
+                f(c.character)
+                    .into_iter()
+                    .map(|character| AlignedChar { character, ..*c }),
+            );
+        }
+        self.chars = output;
+    }
+
+    pub fn filter<F>(&mut self, mut f: F)
+    where
+        F: FnMut(char) -> bool,
+    {
+        self.chars.retain(|c| f(c.character));
+    }
+
+    /// Inserts a character at the start of the text, aligned to an empty original range
+    pub fn prepend(&mut self, character: char) {
+        let original_start = self.chars.first().map_or(0, |c| c.original_start);
+        self.chars.insert(
+            0,
+            AlignedChar {
+                character,
+                original_start,
+                original_end: original_start,
+            },
+        );
+    }
+
+    /// Inserts a character at the end of the text, aligned to an empty original range
+    pub fn append(&mut self, character: char) {
+        let original_end = self.chars.last().map_or(0, |c| c.original_end);
+        self.chars.push(AlignedChar {
+            character,
+            original_start: original_end,
+            original_end,
+        });
+    }
+
+    /// Replaces the characters at `start..end` (character indices), the replacement characters
+    /// are aligned to the union of the original ranges they replace
+    pub fn replace_range(&mut self, start: usize, end: usize, replacement: &str) {
+        let (original_start, original_end) = if start < end {
+            let replaced = &self.chars[start..end];
+            (
+                replaced.iter().map(|c| c.original_start).min().unwrap(),
+                replaced.iter().map(|c| c.original_end).max().unwrap(),
+            )
+        } else {
+            let position = match self.chars.get(start) {
+                Some(c) => c.original_start,
+                None => self.chars.last().map_or(0, |c| c.original_end),
+            };
+            (position, position)
+        };
+        let replacement = replacement.chars().map(|character| AlignedChar {
+            character,
+            original_start,
+            original_end,
+        });
+        self.chars.splice(start..end, replacement);
+    }
+
+    pub fn into_text_and_alignment(self) -> (String, Alignment) {
+        let mut text = String::with_capacity(self.chars.len());
+        let mut alignment = Alignment::default();
+        self.write_text_and_alignment(&mut text, &mut alignment);
+        (text, alignment)
+    }
+
+    /// Same as `into_text_and_alignment`, writing into existing buffers
+    pub fn write_text_and_alignment(&self, text: &mut String, alignment: &mut Alignment) {
+        text.clear();
+        alignment.clear();
+        for c in &self.chars {
+            let start = text.len();
+            text.push(c.character);
+            alignment.push(start, text.len(), c.original_start, c.original_end);
+        }
+    }
+}
+
+/// Rewrites a text before tokenization, keeping the alignment with the original text
+pub trait Normalizer: Send + Sync {
+    fn normalize(&self, normalized: &mut NormalizedString);
+
+    /// Whether whitespace is only ever rewritten alone and into a single whitespace character,
+    /// so that text cut at whitespace normalizes the same in pieces as whole
+    fn keeps_whitespace(&self) -> bool {
+        false
+    }
+}
+
+/// Whether `text` is a single whitespace character
+pub(crate) fn is_single_whitespace(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!((chars.next(), chars.next()), (Some(c), None) if c.is_whitespace())
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NormalizationForm {
+    NFC,
+    NFD,
+    NFKC,
+    NFKD,
+}
+
+/// Unicode normalization.
+///
+/// The text is normalized by segments starting at characters that never interact with the
+/// previous ones, characters of a segment that changed are aligned to the whole segment.
+pub struct UnicodeNormalizer {
+    pub form: NormalizationForm,
+}
+
+impl UnicodeNormalizer {
+    pub fn new(form: NormalizationForm) -> Self {
+        Self { form }
+    }
+
+    /// Whether `c` never interacts with the previous characters, judged from the first character
+    /// of its decomposition: compatibility decompositions can start with combining marks, e.g.
+    /// the halfwidth voiced sound mark `ﾞ` decomposes (NFKD) into U+3099.
+    fn is_segment_start(&self, c: char) -> bool {
+        let first = match self.form {
+            NormalizationForm::NFC | NormalizationForm::NFD => std::iter::once(c).nfd().next(),
+            NormalizationForm::NFKC | NormalizationForm::NFKD => std::iter::once(c).nfkd().next(),
+        };
+        first.map_or(true, |first| {
+            canonical_combining_class(first) == 0
+                && is_nfc_quick(std::iter::once(first)) != IsNormalized::Maybe
+        })
+    }
+
+    fn normalize_segment(&self, segment: &[AlignedChar], output: &mut Vec<AlignedChar>) {
+        let characters = segment.iter().map(|c| c.character);
+        let normalized_characters: Vec<char> = match self.form {
+            NormalizationForm::NFC => characters.nfc().collect(),
+            NormalizationForm::NFD => characters.nfd().collect(),
+            NormalizationForm::NFKC => characters.nfkc().collect(),
+            NormalizationForm::NFKD => characters.nfkd().collect(),
+        };
+        if normalized_characters
+            .iter()
+            .eq(segment.iter().map(|c| &c.character))
+        {
+            output.extend_from_slice(segment);
+        } else {
+            let original_start = segment.iter().map(|c| c.original_start).min().unwrap();
+            let original_end = segment.iter().map(|c| c.original_end).max().unwrap();
+            output.extend(
+                normalized_characters
+                    .into_iter()
+                    .map(|character| AlignedChar {
+                        character,
+                        original_start,
+                        original_end,
+                    }),
+            );
+        }
+    }
+}
+
+impl Normalizer for UnicodeNormalizer {
+    fn keeps_whitespace(&self) -> bool {
+        true
+    }
+
+    fn normalize(&self, normalized: &mut NormalizedString) {
+        let mut output = Vec::with_capacity(normalized.chars.len());
+        let mut segment_start = 0;
+        for end in 1..=normalized.chars.len() {
+            if end == normalized.chars.len()
+                || self.is_segment_start(normalized.chars[end].character)
+            {
+                self.normalize_segment(&normalized.chars[segment_start..end], &mut output);
+                segment_start = end;
+            }
+        }
+        normalized.chars = output;
+    }
+}
+
+pub struct LowercaseNormalizer;
+
+impl Normalizer for LowercaseNormalizer {
+    fn keeps_whitespace(&self) -> bool {
+        true
+    }
+
+    fn normalize(&self, normalized: &mut NormalizedString) {
+        normalized.flat_map(char::to_lowercase);
+    }
+}
+
+/// Decomposes characters (NFD) and removes the combining marks
+pub struct StripAccentsNormalizer;
+
+impl Normalizer for StripAccentsNormalizer {
+    fn keeps_whitespace(&self) -> bool {
+        true
+    }
+
+    fn normalize(&self, normalized: &mut NormalizedString) {
+        UnicodeNormalizer::new(NormalizationForm::NFD).normalize(normalized);
+        normalized.filter(|c| !is_combining_mark(c));
+    }
+}
+
+/// Removes control characters, whitespace control characters (e.g. `\n`) are kept
+pub struct StripControlNormalizer;
+
+impl Normalizer for StripControlNormalizer {
+    fn keeps_whitespace(&self) -> bool {
+        true
+    }
+
+    fn normalize(&self, normalized: &mut NormalizedString) {
+        normalized.filter(|c| !c.is_control() || c.is_whitespace());
+    }
+}
+
+/// Replaces all matches of a regular expression by a fixed string
+pub struct ReplaceNormalizer {
+    pattern: Regex,
+    replacement: String,
+}
+
+impl ReplaceNormalizer {
+    pub fn new(pattern: &str, replacement: &str) -> Result<Self, Box<fancy_regex::Error>> {
+        Ok(Self {
+            pattern: Regex::new(pattern).map_err(Box::new)?,
+            replacement: replacement.to_owned(),
+        })
+    }
+}
+
+impl Normalizer for ReplaceNormalizer {
+    fn normalize(&self, normalized: &mut NormalizedString) {
+        let text = normalized.text();
+        let mut char_starts: Vec<usize> = text.char_indices().map(|(start, _)| start).collect();
+        char_starts.push(text.len());
+        let char_index = |byte: usize| char_starts.binary_search(&byte).unwrap();
+
+        let matches: Vec<(usize, usize)> = self
+            .pattern
+            .find_iter(text.as_str())
+            .flatten()
+            .map(|regex_match| {
+                (
+                    char_index(regex_match.start()),
+                    char_index(regex_match.end()),
+                )
+            })
+            .collect();
+        // Replacing from the end keeps the character indices of earlier matches valid
+        for (start, end) in matches.into_iter().rev() {
+            normalized.replace_range(start, end, self.replacement.as_str());
+        }
+    }
+}
+
+/// Applies normalizers one after the other
+#[derive(Default)]
+pub struct SequenceNormalizer {
+    normalizers: Vec<Box<dyn Normalizer>>,
+}
+
+impl SequenceNormalizer {
+    pub fn new(normalizers: Vec<Box<dyn Normalizer>>) -> Self {
+        Self { normalizers }
+    }
+
+    pub fn push<N>(&mut self, normalizer: N)
+    where
+        N: Normalizer + 'static,
+    {
+        self.normalizers.push(Box::new(normalizer));
+    }
+
+    /// Builds the normalizer for the precompiled charsmap of a specification, `None` if it has
+    /// none. The trainer compiles the `normalization_rule_tsv` rules into the charsmap, the file
+    /// it names is not read (rules can be loaded explicitly with `RuleNormalizer::from_tsv`).
+    pub fn from_normalizer_spec(normalizer_spec: &NormalizerSpec) -> io::Result<Option<Self>> {
+        let precompiled_charsmap = normalizer_spec.get_precompiled_charsmap();
+        if precompiled_charsmap.is_empty() {
+            return Ok(None);
+        }
+        let mut sequence = Self::default();
+        sequence.push(PrecompiledNormalizer::from_bytes(precompiled_charsmap)?);
+        Ok(Some(sequence))
+    }
+}
+
+impl Normalizer for SequenceNormalizer {
+    fn keeps_whitespace(&self) -> bool {
+        self.normalizers
+            .iter()
+            .all(|normalizer| normalizer.keeps_whitespace())
+    }
+
+    fn normalize(&self, normalized: &mut NormalizedString) {
+        for normalizer in self.normalizers.iter() {
+            normalizer.normalize(normalized);
+        }
+    }
+}
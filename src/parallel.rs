@@ -0,0 +1,97 @@
+use crate::bpe_base::BpeTokenizer;
+use rayon::prelude::*;
+
+/// Tokenizes large texts on the rayon thread pool, yielding the same tokens as `tokenize`.
+///
+/// The text is cut into roughly one chunk per thread (and at least `min_chunk_size` bytes), each
+/// cut being moved forward to the next whitespace, where merging can never cross (see
+/// `BpeTokenizer::can_split_on_whitespace`). Chunks are tokenized concurrently and their tokens
+/// concatenated in order. For vocabularies with merges across whitespace the text is tokenized as
+/// a single chunk.
+pub struct ParallelTokenizer<'a, T>
+where
+    T: BpeTokenizer + Sync,
+{
+    tokenizer: &'a T,
+    min_chunk_size: usize,
+    split_on_whitespace: bool,
+    treat_whitespace_as_suffix: bool,
+}
+
+impl<'a, T> ParallelTokenizer<'a, T>
+where
+    T: BpeTokenizer + Sync,
+{
+    pub fn new(tokenizer: &'a T) -> Self {
+        Self {
+            tokenizer,
+            min_chunk_size: 16 * 1024,
+            split_on_whitespace: tokenizer.can_split_on_whitespace('\u{2581}'),
+            treat_whitespace_as_suffix: tokenizer.treat_whitespace_as_suffix(),
+        }
+    }
+
+    pub fn with_min_chunk_size(mut self, min_chunk_size: usize) -> Self {
+        self.min_chunk_size = min_chunk_size;
+        self
+    }
+
+    /// Cuts the text before whitespace (after it if whitespace is treated as suffix)
+    fn split_chunks<'b>(&self, input_text: &'b str) -> Vec<&'b str> {
+        if !self.split_on_whitespace {
+            return vec![input_text];
+        }
+        let chunk_size = self
+            .min_chunk_size
+            .max(input_text.len() / rayon::current_num_threads())
+            .max(1);
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while input_text.len() - start > chunk_size {
+            let mut target = start + chunk_size;
+            while !input_text.is_char_boundary(target) {
+                target += 1;
+            }
+            let cut = input_text[target..]
+                .char_indices()
+                .find(|(_, c)| c.is_whitespace())
+                .map(|(position, c)| {
+                    if self.treat_whitespace_as_suffix {
+                        target + position + c.len_utf8()
+                    } else {
+                        target + position
+                    }
+                });
+            match cut {
+                Some(cut) if cut < input_text.len() => {
+                    chunks.push(&input_text[start..cut]);
+                    start = cut;
+                }
+                _ => break,
+            }
+        }
+        chunks.push(&input_text[start..]);
+        chunks
+    }
+
+    pub fn tokenize<'b>(&self, input_text: &'b str) -> Vec<&'b str> {
+        let chunks = self.split_chunks(input_text);
+        let last_chunk_index = chunks.len() - 1;
+        let chunk_tokens: Vec<Vec<&'b str>> = chunks
+            .into_par_iter()
+            .enumerate()
+            .map(|(chunk_index, chunk)| {
+                // The dummy whitespace token belongs to the first chunk (last if suffix)
+                let add_dummy_whitespace = self.tokenizer.add_dummy_prefix()
+                    && if self.treat_whitespace_as_suffix {
+                        chunk_index == last_chunk_index
+                    } else {
+                        chunk_index == 0
+                    };
+                self.tokenizer.tokenize_chunk(chunk, add_dummy_whitespace)
+            })
+            .collect();
+        chunk_tokens.concat()
+    }
+}
@@ -0,0 +1,133 @@
+use crate::bpe_base::{read_model_proto, BpeTokenizer, Symbol, TokenizerConfig};
+use crate::naive_bpe::NaiveBpeTokenizer;
+use crate::pre_tokenizer::{PreTokenizer, SequencePreTokenizer, WhitespacePunctuationPreTokenizer};
+use crate::priority_queue_bpe::PriorityQueueBpeTokenizer;
+use crate::priority_queue_linked_list_bpe::PriorityQueueBpeLLTokenizer;
+use crate::proto::sentencepiece_model::ModelProto;
+use crate::word_cache::{CacheStats, WordCache};
+use protobuf::ProtobufError;
+use std::path::Path;
+
+pub type NaivePreSplitBpeTokenizer = PreSplitBpeTokenizer<NaiveBpeTokenizer>;
+pub type PriorityQueuePreSplitBpeTokenizer = PreSplitBpeTokenizer<PriorityQueueBpeTokenizer>;
+pub type PriorityQueuePreSplitBpeLLTokenizer = PreSplitBpeTokenizer<PriorityQueueBpeLLTokenizer>;
+
+/// Splits the pre-processed text in words (on whitespace and ASCII punctuation by default) and
+/// runs the merges of the wrapped tokenizer on each word separately.
+pub struct PreSplitBpeTokenizer<T>
+where
+    T: BpeTokenizer,
+{
+    tokenizer: T,
+    pre_tokenizer: Box<dyn PreTokenizer>,
+    cache: Option<WordCache>,
+}
+
+impl<T> PreSplitBpeTokenizer<T>
+where
+    T: BpeTokenizer,
+{
+    pub fn new(merges_path: &Path) -> Result<Self, ProtobufError> {
+        Self::from_model_proto(&read_model_proto(merges_path)?)
+    }
+
+    pub fn from_tokenizer(tokenizer: T) -> Self {
+        let pre_tokenizer = WhitespacePunctuationPreTokenizer {
+            treat_whitespace_as_suffix: tokenizer.treat_whitespace_as_suffix(),
+            ..Default::default()
+        };
+        Self {
+            tokenizer,
+            pre_tokenizer: Box::new(pre_tokenizer),
+            cache: None,
+        }
+    }
+
+    /// Builds the tokenizer of a model pre-splitting as its trainer did, following the `split_*`
+    /// flags of the trainer specification
+    pub fn from_model_proto_with_trainer_spec(
+        model_proto: &ModelProto,
+    ) -> Result<Self, ProtobufError> {
+        let pre_tokenizer = SequencePreTokenizer::from_trainer_spec(model_proto.get_trainer_spec());
+        Ok(Self::from_model_proto(model_proto)?.with_pre_tokenizer(pre_tokenizer))
+    }
+
+    pub fn with_pre_tokenizer<P>(mut self, pre_tokenizer: P) -> Self
+    where
+        P: PreTokenizer + 'static,
+    {
+        self.pre_tokenizer = Box::new(pre_tokenizer);
+        self
+    }
+
+    /// Caches the merged symbols of up to `capacity` pre-split words
+    pub fn with_cache(mut self, capacity: usize) -> Self {
+        self.cache = Some(WordCache::new(capacity));
+        self
+    }
+
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(WordCache::stats)
+    }
+
+    /// Appends the merged symbols of a word, relative to its start, to `symbols`
+    fn merge_word(&self, word: &str, symbols: &mut Vec<Symbol>) {
+        match &self.cache {
+            Some(cache) => {
+                if !cache.get(word, symbols) {
+                    let word_start = symbols.len();
+                    symbols.extend(self.tokenizer.apply_merges(word));
+                    cache.insert(word, &symbols[word_start..]);
+                }
+            }
+            None => symbols.extend(self.tokenizer.apply_merges(word)),
+        }
+    }
+}
+
+impl<T> BpeTokenizer for PreSplitBpeTokenizer<T>
+where
+    T: BpeTokenizer,
+{
+    fn from_config(config: TokenizerConfig) -> Self {
+        Self::from_tokenizer(T::from_config(config))
+    }
+
+    fn config(&self) -> &TokenizerConfig {
+        self.tokenizer.config()
+    }
+
+    fn config_mut(&mut self) -> &mut TokenizerConfig {
+        self.tokenizer.config_mut()
+    }
+
+    /// Merges never cross words, either the pre-tokenizer or the vocabulary of the wrapped
+    /// tokenizer can rule out merges across whitespace
+    fn can_split_on_whitespace(&self, whitespace_token: char) -> bool {
+        let keeps_whitespace = self
+            .get_normalizer()
+            .map_or(true, |normalizer| normalizer.keeps_whitespace());
+        keeps_whitespace
+            && self
+                .pre_tokenizer
+                .splits_on_whitespace(whitespace_token, self.treat_whitespace_as_suffix())
+            || self.tokenizer.can_split_on_whitespace(whitespace_token)
+    }
+
+    fn apply_merges(&self, text: &str) -> Vec<Symbol> {
+        let split_texts = self.pre_tokenizer.pre_tokenize(text);
+
+        let mut output = Vec::new();
+        let mut offset = 0;
+        for split_text in split_texts {
+            let word_start = output.len();
+            self.merge_word(split_text, &mut output);
+            for symbol in output[word_start..].iter_mut() {
+                symbol.start_byte += offset;
+                symbol.end_byte += offset;
+            }
+            offset += split_text.len();
+        }
+        output
+    }
+}
@@ -0,0 +1,365 @@
+use crate::proto::sentencepiece_model::TrainerSpec;
+use fancy_regex::Regex;
+use unicode_general_category::{get_general_category, GeneralCategory};
+use unicode_script::{Script, UnicodeScript};
+
+/// GPT-2 split pattern, written for pre-processed text where whitespace has been replaced by `▁`
+pub const GPT2_PATTERN: &str =
+    r"'s|'t|'re|'ve|'m|'ll|'d|▁?\p{L}+|▁?\p{N}+|▁?[^▁\p{L}\p{N}]+|▁+(?![^▁])|▁+";
+
+/// cl100k split pattern, written for pre-processed text where whitespace has been replaced by `▁`
+pub const CL100K_PATTERN: &str =
+    r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\p{L}\p{N}]?\p{L}+|\p{N}{1,3}|▁?[^▁\p{L}\p{N}]+|▁+(?![^▁])|▁+";
+
+/// Splits a pre-processed text in words, merges are never applied across words.
+///
+/// The words returned must cover the input text in order, without gaps or overlaps.
+pub trait PreTokenizer: Send + Sync {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<&'a str>;
+
+    /// Whether every whitespace token starts a word (ends one if whitespace is treated as
+    /// suffix), so that merges can never cross it whatever the vocabulary
+    fn splits_on_whitespace(
+        &self,
+        _whitespace_token: char,
+        _treat_whitespace_as_suffix: bool,
+    ) -> bool {
+        false
+    }
+}
+
+/// Splits `text` before every character for which `is_boundary(previous, current)` is true
+fn split_on_boundaries<F>(text: &str, mut is_boundary: F) -> Vec<&str>
+where
+    F: FnMut(char, char) -> bool,
+{
+    let mut output = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    for (c_pos, c) in text.char_indices() {
+        if let Some(previous) = previous {
+            if is_boundary(previous, c) {
+                output.push(&text[start..c_pos]);
+                start = c_pos;
+            }
+        }
+        previous = Some(c);
+    }
+    if start < text.len() {
+        output.push(&text[start..]);
+    }
+    output
+}
+
+pub fn split_whitespace_punctuation(input_string: &str, whitespace_token: char) -> Vec<&str> {
+    let mut output: Vec<&str> = Vec::new();
+    let mut start: usize = 0;
+
+    for (c_pos, c) in input_string.char_indices() {
+        if c == whitespace_token {
+            if start < c_pos {
+                output.push(&input_string[start..c_pos]);
+            }
+            start = c_pos;
+        } else if c.is_ascii_punctuation() {
+            if start < c_pos {
+                output.push(&input_string[start..c_pos]);
+            }
+            output.push(&input_string[c_pos..c_pos + c.len_utf8()]);
+            start = c_pos + c.len_utf8();
+        }
+    }
+    if start < input_string.len() {
+        output.push(&input_string[start..]);
+    }
+    output
+}
+
+/// Splits before whitespace tokens (after them if whitespace is treated as suffix) and isolates
+/// ASCII punctuation (default pre-splitting)
+pub struct WhitespacePunctuationPreTokenizer {
+    pub whitespace_token: char,
+    pub treat_whitespace_as_suffix: bool,
+}
+
+impl Default for WhitespacePunctuationPreTokenizer {
+    fn default() -> Self {
+        Self {
+            whitespace_token: '\u{2581}',
+            treat_whitespace_as_suffix: false,
+        }
+    }
+}
+
+impl PreTokenizer for WhitespacePunctuationPreTokenizer {
+    fn splits_on_whitespace(
+        &self,
+        whitespace_token: char,
+        treat_whitespace_as_suffix: bool,
+    ) -> bool {
+        self.whitespace_token == whitespace_token
+            && self.treat_whitespace_as_suffix == treat_whitespace_as_suffix
+    }
+
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        if self.treat_whitespace_as_suffix {
+            split_on_boundaries(text, |previous, c| {
+                previous == self.whitespace_token
+                    || previous.is_ascii_punctuation()
+                    || c.is_ascii_punctuation()
+            })
+        } else {
+            split_whitespace_punctuation(text, self.whitespace_token)
+        }
+    }
+}
+
+/// Splits before whitespace tokens, keeping the token at the start of the following word (at
+/// the end of the previous word if whitespace is treated as suffix)
+pub struct WhitespacePreTokenizer {
+    pub whitespace_token: char,
+    pub treat_whitespace_as_suffix: bool,
+}
+
+impl Default for WhitespacePreTokenizer {
+    fn default() -> Self {
+        Self {
+            whitespace_token: '\u{2581}',
+            treat_whitespace_as_suffix: false,
+        }
+    }
+}
+
+impl PreTokenizer for WhitespacePreTokenizer {
+    fn splits_on_whitespace(
+        &self,
+        whitespace_token: char,
+        treat_whitespace_as_suffix: bool,
+    ) -> bool {
+        self.whitespace_token == whitespace_token
+            && self.treat_whitespace_as_suffix == treat_whitespace_as_suffix
+    }
+
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        if self.treat_whitespace_as_suffix {
+            split_on_boundaries(text, |previous, _| previous == self.whitespace_token)
+        } else {
+            split_on_boundaries(text, |_, c| c == self.whitespace_token)
+        }
+    }
+}
+
+/// Splits on matches of a regular expression, text between matches is kept as separate words
+pub struct RegexPreTokenizer {
+    regex: Regex,
+}
+
+impl RegexPreTokenizer {
+    pub fn new(pattern: &str) -> Result<Self, Box<fancy_regex::Error>> {
+        Ok(Self {
+            regex: Regex::new(pattern).map_err(Box::new)?,
+        })
+    }
+
+    pub fn gpt2() -> Self {
+        Self::new(GPT2_PATTERN).unwrap()
+    }
+
+    pub fn cl100k() -> Self {
+        Self::new(CL100K_PATTERN).unwrap()
+    }
+}
+
+impl PreTokenizer for RegexPreTokenizer {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let mut output = Vec::new();
+        let mut start = 0;
+        for regex_match in self.regex.find_iter(text) {
+            // Matching errors (backtracking limit) leave the rest of the text as a single word
+            let regex_match = match regex_match {
+                Ok(regex_match) => regex_match,
+                Err(_) => break,
+            };
+            if start < regex_match.start() {
+                output.push(&text[start..regex_match.start()]);
+            }
+            if regex_match.start() < regex_match.end() {
+                output.push(regex_match.as_str());
+            }
+            start = regex_match.end();
+        }
+        if start < text.len() {
+            output.push(&text[start..]);
+        }
+        output
+    }
+}
+
+/// Isolates every digit (`TrainerSpec.split_digits`)
+pub struct DigitsPreTokenizer;
+
+impl PreTokenizer for DigitsPreTokenizer {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        split_on_boundaries(text, |previous, c| previous.is_numeric() || c.is_numeric())
+    }
+}
+
+/// Splits numbers from surrounding characters (`TrainerSpec.split_by_number`), optionally
+/// grouping long numbers in chunks of at most `max_group_length` digits
+#[derive(Default)]
+pub struct NumbersPreTokenizer {
+    pub max_group_length: Option<usize>,
+}
+
+impl NumbersPreTokenizer {
+    pub fn with_max_group_length(max_group_length: usize) -> Self {
+        Self {
+            max_group_length: Some(max_group_length),
+        }
+    }
+}
+
+impl PreTokenizer for NumbersPreTokenizer {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        // Number of digits in the group ending with `previous`
+        let mut group_length = 0;
+        split_on_boundaries(text, |previous, c| {
+            if previous.is_numeric() {
+                group_length += 1;
+            }
+            let boundary = previous.is_numeric() != c.is_numeric()
+                || (c.is_numeric() && Some(group_length) == self.max_group_length);
+            if boundary || !previous.is_numeric() {
+                group_length = 0;
+            }
+            boundary
+        })
+    }
+}
+
+/// Splits where the Unicode script changes (`TrainerSpec.split_by_unicode_script`).
+///
+/// As in sentencepiece, `Common` and `Inherited` characters (whitespace, punctuation, combining
+/// marks) take the script of the previous character and Han, Hiragana and Katakana are considered
+/// a single script.
+pub struct UnicodeScriptPreTokenizer;
+
+impl UnicodeScriptPreTokenizer {
+    fn get_script(c: char) -> Option<Script> {
+        match c.script() {
+            Script::Common | Script::Inherited => None,
+            Script::Hiragana | Script::Katakana => Some(Script::Han),
+            script => Some(script),
+        }
+    }
+}
+
+impl PreTokenizer for UnicodeScriptPreTokenizer {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        // Last script other than `Common` or `Inherited` seen up to `previous`
+        let mut current_script = None;
+        split_on_boundaries(text, |previous, c| {
+            if let Some(script) = Self::get_script(previous) {
+                current_script = Some(script);
+            }
+            match (current_script, Self::get_script(c)) {
+                (Some(current_script), Some(script)) => current_script != script,
+                _ => false,
+            }
+        })
+    }
+}
+
+/// Isolates characters from the given Unicode punctuation classes (all of them by default)
+pub struct PunctuationPreTokenizer {
+    pub categories: Vec<GeneralCategory>,
+}
+
+impl Default for PunctuationPreTokenizer {
+    fn default() -> Self {
+        Self {
+            categories: vec![
+                GeneralCategory::ConnectorPunctuation,
+                GeneralCategory::DashPunctuation,
+                GeneralCategory::OpenPunctuation,
+                GeneralCategory::ClosePunctuation,
+                GeneralCategory::InitialPunctuation,
+                GeneralCategory::FinalPunctuation,
+                GeneralCategory::OtherPunctuation,
+            ],
+        }
+    }
+}
+
+impl PreTokenizer for PunctuationPreTokenizer {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let is_punctuation = |c: char| self.categories.contains(&get_general_category(c));
+        split_on_boundaries(text, |previous, c| {
+            is_punctuation(previous) || is_punctuation(c)
+        })
+    }
+}
+
+/// Applies pre-tokenizers one after the other, each splitting the words of the previous one
+#[derive(Default)]
+pub struct SequencePreTokenizer {
+    pre_tokenizers: Vec<Box<dyn PreTokenizer>>,
+}
+
+impl SequencePreTokenizer {
+    pub fn new(pre_tokenizers: Vec<Box<dyn PreTokenizer>>) -> Self {
+        Self { pre_tokenizers }
+    }
+
+    pub fn push<P>(&mut self, pre_tokenizer: P)
+    where
+        P: PreTokenizer + 'static,
+    {
+        self.pre_tokenizers.push(Box::new(pre_tokenizer));
+    }
+
+    /// Builds the pre-tokenizers matching the `split_*` flags of the trainer specification
+    pub fn from_trainer_spec(trainer_spec: &TrainerSpec) -> Self {
+        let mut sequence = Self::default();
+        if trainer_spec.get_split_by_whitespace() {
+            sequence.push(WhitespacePreTokenizer {
+                treat_whitespace_as_suffix: trainer_spec.get_treat_whitespace_as_suffix(),
+                ..Default::default()
+            });
+        }
+        if trainer_spec.get_split_by_unicode_script() {
+            sequence.push(UnicodeScriptPreTokenizer);
+        }
+        if trainer_spec.get_split_by_number() {
+            sequence.push(NumbersPreTokenizer::default());
+        }
+        if trainer_spec.get_split_digits() {
+            sequence.push(DigitsPreTokenizer);
+        }
+        sequence
+    }
+}
+
+impl PreTokenizer for SequencePreTokenizer {
+    /// Later pre-tokenizers only split words further, one splitting on whitespace is enough
+    fn splits_on_whitespace(
+        &self,
+        whitespace_token: char,
+        treat_whitespace_as_suffix: bool,
+    ) -> bool {
+        self.pre_tokenizers.iter().any(|pre_tokenizer| {
+            pre_tokenizer.splits_on_whitespace(whitespace_token, treat_whitespace_as_suffix)
+        })
+    }
+
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let mut words = vec![text];
+        for pre_tokenizer in self.pre_tokenizers.iter() {
+            words = words
+                .into_iter()
+                .flat_map(|word| pre_tokenizer.pre_tokenize(word))
+                .collect();
+        }
+        words
+    }
+}
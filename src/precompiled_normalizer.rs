@@ -0,0 +1,160 @@
+use crate::normalizer::{is_single_whitespace, AlignedChar, NormalizedString, Normalizer};
+use std::convert::TryInto;
+use std::io;
+
+/// Rewriter for sentencepiece `precompiled_charsmap` blobs.
+///
+/// The blob holds the size of a Darts double-array trie (u32, little endian), the trie indexed
+/// by UTF-8 bytes and a buffer of null-terminated replacement strings the trie values point to.
+/// As for rules, the longest match in the trie is always applied.
+pub struct PrecompiledNormalizer {
+    trie: Vec<u32>,
+    normalized: Vec<u8>,
+}
+
+impl PrecompiledNormalizer {
+    pub fn from_bytes(precompiled_charsmap: &[u8]) -> io::Result<Self> {
+        let invalid_charsmap =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_owned());
+        if precompiled_charsmap.len() < 4 {
+            return Err(invalid_charsmap("charsmap is missing the trie size"));
+        }
+        let trie_size = u32::from_le_bytes(precompiled_charsmap[..4].try_into().unwrap()) as usize;
+        if trie_size % 4 != 0 || precompiled_charsmap.len() - 4 < trie_size {
+            return Err(invalid_charsmap("invalid trie size"));
+        }
+        let trie = precompiled_charsmap[4..4 + trie_size]
+            .chunks_exact(4)
+            .map(|unit| u32::from_le_bytes(unit.try_into().unwrap()))
+            .collect();
+        let normalized = precompiled_charsmap[4 + trie_size..].to_vec();
+        Ok(Self { trie, normalized })
+    }
+
+    fn has_leaf(unit: u32) -> bool {
+        (unit >> 8) & 1 == 1
+    }
+
+    fn value(unit: u32) -> u32 {
+        unit & ((1 << 31) - 1)
+    }
+
+    fn label(unit: u32) -> u32 {
+        unit & ((1 << 31) | 0xFF)
+    }
+
+    fn offset(unit: u32) -> usize {
+        ((unit >> 10) << ((unit & (1 << 9)) >> 6)) as usize
+    }
+
+    /// Returns the length (in bytes) and replacement of the longest entry prefixing `key`
+    fn longest_match(&self, key: &[u8]) -> Option<(usize, &str)> {
+        let mut longest_match = None;
+        let mut node_position = Self::offset(*self.trie.first()?);
+        for (index, &byte) in key.iter().enumerate() {
+            if byte == 0 {
+                break;
+            }
+            node_position ^= byte as usize;
+            let unit = match self.trie.get(node_position) {
+                Some(&unit) if Self::label(unit) == byte as u32 => unit,
+                _ => break,
+            };
+            node_position ^= Self::offset(unit);
+            if Self::has_leaf(unit) {
+                match self.trie.get(node_position) {
+                    Some(&leaf) => longest_match = Some((index + 1, Self::value(leaf) as usize)),
+                    None => break,
+                }
+            }
+        }
+        let (length, value) = longest_match?;
+        Some((length, self.replacement(value)?))
+    }
+
+    /// Null-terminated replacement starting at `value` in the replacement buffer
+    fn replacement(&self, value: usize) -> Option<&str> {
+        let replacement = self.normalized.get(value..)?;
+        let replacement_end = replacement.iter().position(|&byte| byte == 0)?;
+        std::str::from_utf8(&replacement[..replacement_end]).ok()
+    }
+
+    /// Every key of the trie with its value, `None` if the trie is not a tree
+    fn entries(&self) -> Option<Vec<(Vec<u8>, usize)>> {
+        let mut entries = Vec::new();
+        let mut visited = vec![false; self.trie.len()];
+        let mut stack = vec![(Self::offset(*self.trie.first()?), Vec::new())];
+        while let Some((node_position, key)) = stack.pop() {
+            for byte in 1..=u8::MAX {
+                let child_position = node_position ^ byte as usize;
+                let unit = match self.trie.get(child_position) {
+                    Some(&unit) if Self::label(unit) == byte as u32 => unit,
+                    _ => continue,
+                };
+                if std::mem::replace(&mut visited[child_position], true) {
+                    return None;
+                }
+                let mut child_key = key.clone();
+                child_key.push(byte);
+                let child_node_position = child_position ^ Self::offset(unit);
+                if Self::has_leaf(unit) {
+                    let leaf = *self.trie.get(child_node_position)?;
+                    entries.push((child_key.clone(), Self::value(leaf) as usize));
+                }
+                stack.push((child_node_position, child_key));
+            }
+        }
+        Some(entries)
+    }
+}
+
+impl Normalizer for PrecompiledNormalizer {
+    /// Keys that are not whole characters or have an invalid replacement are never applied
+    fn keeps_whitespace(&self) -> bool {
+        self.entries().is_some_and(|entries| {
+            entries.iter().all(|(key, value)| {
+                match (std::str::from_utf8(key), self.replacement(*value)) {
+                    (Ok(key), Some(replacement)) => {
+                        !key.contains(char::is_whitespace)
+                            || is_single_whitespace(key) && is_single_whitespace(replacement)
+                    }
+                    _ => true,
+                }
+            })
+        })
+    }
+
+    fn normalize(&self, normalized: &mut NormalizedString) {
+        let text = normalized.text();
+        let mut output = Vec::with_capacity(normalized.chars.len());
+        let mut position = 0;
+        let mut char_index = 0;
+        while position < text.len() {
+            let matched = self
+                .longest_match(&text.as_bytes()[position..])
+                .filter(|(length, _)| text.is_char_boundary(position + length));
+            match matched {
+                Some((length, replacement)) => {
+                    let n_chars = text[position..position + length].chars().count();
+                    let source = &normalized.chars[char_index..char_index + n_chars];
+                    let original_start = source.iter().map(|c| c.original_start).min().unwrap();
+                    let original_end = source.iter().map(|c| c.original_end).max().unwrap();
+                    output.extend(replacement.chars().map(|character| AlignedChar {
+                        character,
+                        original_start,
+                        original_end,
+                    }));
+                    position += length;
+                    char_index += n_chars;
+                }
+                None => {
+                    let c = normalized.chars[char_index];
+                    output.push(c);
+                    position += c.character.len_utf8();
+                    char_index += 1;
+                }
+            }
+        }
+        normalized.chars = output;
+    }
+}
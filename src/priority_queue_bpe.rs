@@ -1,4 +1,7 @@
-use crate::bpe_base::{BpeTokenizer, MergesVocab, Symbol, SymbolPair};
+use crate::bpe_base::{read_model_proto, BpeTokenizer, Symbol, SymbolPair, TokenizerConfig};
+use crate::merge_table::MergeTable;
+use crate::merge_trace::MergeTrace;
+use crate::profiling::{AgendaRecorder, AgendaStats};
 use itertools::Itertools;
 use protobuf::ProtobufError;
 use std::collections::btree_set::Iter as BTreeSetIter;
@@ -19,29 +22,30 @@ impl IntoIterator for SymbolBTree {
 }
 
 impl SymbolBTree {
-    pub fn from_text(input_text: &str) -> Self {
-        let mut symbols = BTreeSet::new();
-        for (character_start, character) in input_text.char_indices() {
-            symbols.insert(Symbol {
-                start_byte: character_start,
-                end_byte: character_start + character.len_utf8(),
-            });
+    pub fn from_text(input_text: &str, merge_table: &MergeTable) -> Self {
+        Self {
+            symbols: merge_table.char_symbols(input_text).collect(),
         }
-        Self { symbols }
     }
 
-    pub fn merge_symbols(&mut self, symbol_1: &Symbol, symbol_2: &Symbol) -> Symbol {
+    pub fn merge_symbols(
+        &mut self,
+        symbol_1: &Symbol,
+        symbol_2: &Symbol,
+        merged_id: i64,
+    ) -> Symbol {
         self.symbols.remove(symbol_1);
         self.symbols.remove(symbol_2);
         let new_symbol = Symbol {
             start_byte: symbol_1.start_byte,
             end_byte: symbol_2.end_byte,
+            id: merged_id,
         };
         self.symbols.insert(new_symbol);
         new_symbol
     }
 
-    pub fn iter(&self) -> BTreeSetIter<Symbol> {
+    pub fn iter(&self) -> BTreeSetIter<'_, Symbol> {
         self.symbols.iter()
     }
 
@@ -51,68 +55,105 @@ impl SymbolBTree {
 }
 
 pub struct PriorityQueueBpeTokenizer {
-    merges_vocab: MergesVocab,
+    config: TokenizerConfig,
 }
 
 impl PriorityQueueBpeTokenizer {
     pub fn new(merges_path: &Path) -> Result<Self, ProtobufError> {
-        let merges_vocab = Self::read_proto(merges_path)?;
-        Ok(Self { merges_vocab })
+        Self::from_model_proto(&read_model_proto(merges_path)?)
     }
 
-    fn maybe_add_pair(
+    fn maybe_add_pair<R: AgendaRecorder>(
         &self,
         left_symbol: &Symbol,
         right_symbol: &Symbol,
-        input_text: &str,
         agenda: &mut BinaryHeap<SymbolPair>,
+        agenda_recorder: &mut R,
     ) {
-        let merged_text = &input_text[left_symbol.start_byte..right_symbol.end_byte];
-        if let Some(&score) = self.merges_vocab.get(merged_text) {
+        if let Some((score, merged_id)) = self.get_merge(left_symbol, right_symbol) {
             agenda.push(SymbolPair {
                 left: *left_symbol,
                 right: *right_symbol,
                 score,
-            })
+                merged_id,
+            });
+            agenda_recorder.record_push(agenda.len());
         }
     }
-}
 
-impl BpeTokenizer for PriorityQueueBpeTokenizer {
-    fn get_merges_vocab(&self) -> &MergesVocab {
-        &self.merges_vocab
+    /// Counts the pairs going through the agenda when tokenizing a text. Pairs are left on the
+    /// agenda when one of their symbols is merged and only skipped once popped.
+    pub fn agenda_stats(&self, input_text: &str) -> AgendaStats {
+        let (text, _) = self.pre_process_text(input_text, '\u{2581}', self.config.add_dummy_prefix);
+        let mut agenda_stats = AgendaStats::default();
+        self.merge(&text, &mut agenda_stats, None);
+        agenda_stats
     }
 
-    fn tokenize<'a>(&self, input_text: &'a str) -> Vec<&'a str> {
-        let (text, byte_mapping) = self.pre_process_text(input_text, '\u{2581}');
+    /// Records the merges made when tokenizing a text
+    pub fn explain(&self, input_text: &str) -> MergeTrace {
+        let (text, _) = self.pre_process_text(input_text, '\u{2581}', self.config.add_dummy_prefix);
+        let mut merge_trace = MergeTrace::new(&text);
+        self.merge(&text, &mut (), Some(&mut merge_trace));
+        merge_trace
+    }
 
-        let mut symbols = SymbolBTree::from_text(text.as_str());
+    fn merge<R: AgendaRecorder>(
+        &self,
+        text: &str,
+        agenda_recorder: &mut R,
+        mut merge_trace: Option<&mut MergeTrace>,
+    ) -> Vec<Symbol> {
+        let mut symbols = SymbolBTree::from_text(text, &self.config.merge_table);
         let mut agenda: BinaryHeap<SymbolPair> = BinaryHeap::new();
 
         for (left_symbol, right_symbol) in symbols.iter().tuple_windows::<(&Symbol, &Symbol)>() {
-            self.maybe_add_pair(left_symbol, right_symbol, text.as_str(), &mut agenda);
+            self.maybe_add_pair(left_symbol, right_symbol, &mut agenda, agenda_recorder);
         }
         while let Some(symbol_pair) = agenda.pop() {
             let left_symbol = symbols.get(&symbol_pair.left).cloned();
             let right_symbol = symbols.get(&symbol_pair.right).cloned();
 
             if let (Some(left_symbol), Some(right_symbol)) = (left_symbol, right_symbol) {
-                let new_symbol = symbols.merge_symbols(&left_symbol, &right_symbol);
+                let new_symbol =
+                    symbols.merge_symbols(&left_symbol, &right_symbol, symbol_pair.merged_id);
+                if let Some(merge_trace) = merge_trace.as_deref_mut() {
+                    merge_trace.record_merge(
+                        left_symbol.byte_range(),
+                        right_symbol.byte_range(),
+                        symbol_pair.score,
+                        symbols.iter().map(Symbol::byte_range),
+                    );
+                }
                 if let Some(next) = symbols.symbols.range(new_symbol..).nth(1) {
-                    self.maybe_add_pair(&new_symbol, next, text.as_str(), &mut agenda);
+                    self.maybe_add_pair(&new_symbol, next, &mut agenda, agenda_recorder);
                 }
                 if let Some(prev) = symbols.symbols.range(..new_symbol).next_back() {
-                    self.maybe_add_pair(prev, &new_symbol, text.as_str(), &mut agenda);
+                    self.maybe_add_pair(prev, &new_symbol, &mut agenda, agenda_recorder);
                 }
+            } else {
+                agenda_recorder.record_stale();
             }
         }
 
-        let mut output = Vec::new();
-        for symbol in symbols {
-            output.push(
-                &input_text[byte_mapping[&symbol.start_byte]..byte_mapping[&symbol.end_byte]],
-            );
-        }
-        output
+        symbols.into_iter().collect()
+    }
+}
+
+impl BpeTokenizer for PriorityQueueBpeTokenizer {
+    fn from_config(config: TokenizerConfig) -> Self {
+        Self { config }
+    }
+
+    fn config(&self) -> &TokenizerConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut TokenizerConfig {
+        &mut self.config
+    }
+
+    fn apply_merges(&self, text: &str) -> Vec<Symbol> {
+        self.merge(text, &mut (), None)
     }
 }
@@ -1,4 +1,8 @@
-use crate::bpe_base::{BpeTokenizer, MergesVocab};
+use crate::bpe_base::{read_model_proto, BpeTokenizer, Symbol, TokenizerConfig};
+use crate::merge_table::MergeTable;
+use crate::merge_trace::MergeTrace;
+use crate::profiling::{AgendaRecorder, AgendaStats};
+use crate::workspace::Workspace;
 use protobuf::ProtobufError;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
@@ -12,6 +16,7 @@ pub struct SymbolNode {
     pub prev: isize,
     pub next: isize,
     pub size: usize,
+    pub id: i64,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -37,6 +42,7 @@ impl PartialOrd for SymbolNodePair {
     }
 }
 
+#[derive(Default)]
 pub struct SymbolList {
     symbols: Vec<Option<SymbolNode>>,
 }
@@ -59,23 +65,25 @@ impl IntoIterator for SymbolList {
 }
 
 impl SymbolList {
-    pub fn from_text(input_text: &str) -> Self {
-        let mut symbols = Vec::with_capacity(input_text.len());
-        for (index, (character_start, character)) in input_text.char_indices().enumerate() {
-            let next = if index == input_text.char_indices().count() {
+    /// Replaces the symbols by the characters of `input_text`, reusing the allocated buffer
+    pub fn reset(&mut self, input_text: &str, merge_table: &MergeTable) {
+        self.symbols.clear();
+        let n_characters = input_text.chars().count();
+        for (index, symbol) in merge_table.char_symbols(input_text).enumerate() {
+            let next = if index + 1 == n_characters {
                 -1
             } else {
                 (index + 1) as isize
             };
-            symbols.push(Some(SymbolNode {
-                start_byte: character_start,
-                end_byte: character_start + character.len_utf8(),
+            self.symbols.push(Some(SymbolNode {
+                start_byte: symbol.start_byte,
+                end_byte: symbol.end_byte,
                 prev: index as isize - 1,
                 next,
                 size: 1,
+                id: symbol.id,
             }));
         }
-        Self { symbols }
     }
 
     pub fn len(&self) -> usize {
@@ -87,6 +95,7 @@ impl SymbolList {
         symbol_1_index: usize,
         symbol_2_index: usize,
         size_validation: usize,
+        merge_table: &MergeTable,
     ) -> Option<SymbolNode> {
         if let (Some(left_symbol), Some(right_symbol)) =
             (self[symbol_1_index], self[symbol_2_index])
@@ -105,6 +114,7 @@ impl SymbolList {
                 prev: left_symbol.prev,
                 next: right_symbol.next,
                 size: left_symbol.size + right_symbol.size,
+                id: merge_table.get_merge(left_symbol.id, right_symbol.id)?.1,
             };
             self.symbols[symbol_2_index] = None;
             self.symbols[symbol_1_index] = Some(new_symbol);
@@ -116,60 +126,94 @@ impl SymbolList {
 }
 
 pub struct PriorityQueueBpeLLTokenizer {
-    merges_vocab: MergesVocab,
+    config: TokenizerConfig,
 }
 
 impl PriorityQueueBpeLLTokenizer {
     pub fn new(merges_path: &Path) -> Result<Self, ProtobufError> {
-        let merges_vocab = Self::read_proto(merges_path)?;
-        Ok(Self { merges_vocab })
+        Self::from_model_proto(&read_model_proto(merges_path)?)
     }
 
-    fn maybe_add_pair(
-        &self,
+    fn maybe_add_pair<R: AgendaRecorder>(
+        merge_table: &MergeTable,
         left_symbol_index: isize,
         right_symbol_index: isize,
-        input_text: &str,
         symbols: &SymbolList,
         agenda: &mut BinaryHeap<SymbolNodePair>,
+        agenda_recorder: &mut R,
     ) {
         if left_symbol_index != -1 && right_symbol_index != -1 {
             if let (Some(left_symbol), Some(right_symbol)) = (
                 symbols[left_symbol_index as usize],
                 symbols[right_symbol_index as usize],
             ) {
-                let merged_text = &input_text[left_symbol.start_byte..right_symbol.end_byte];
-                if let Some(&score) = self.merges_vocab.get(merged_text) {
+                if let Some((score, _)) = merge_table.get_merge(left_symbol.id, right_symbol.id) {
                     agenda.push(SymbolNodePair {
                         left: left_symbol_index,
                         right: right_symbol_index,
                         score,
                         pair_size: left_symbol.size + right_symbol.size,
-                    })
+                    });
+                    agenda_recorder.record_push(agenda.len());
                 }
             }
         }
     }
-}
 
-impl BpeTokenizer for PriorityQueueBpeLLTokenizer {
-    fn get_merges_vocab(&self) -> &MergesVocab {
-        &self.merges_vocab
+    /// Agenda usage when tokenizing a text. Stale pairs are detected on pop by their size no
+    /// longer matching the symbols at their indices.
+    pub fn agenda_stats(&self, input_text: &str) -> AgendaStats {
+        let (text, _) = self.pre_process_text(input_text, '\u{2581}', self.config.add_dummy_prefix);
+        let mut agenda_stats = AgendaStats::default();
+        Self::merge_into(
+            &self.config.merge_table,
+            &text,
+            &mut Workspace::new(),
+            &mut Vec::new(),
+            &mut agenda_stats,
+            None,
+        );
+        agenda_stats
     }
 
-    fn tokenize<'a>(&self, input_text: &'a str) -> Vec<&'a str> {
-        let (text, byte_mapping) = self.pre_process_text(input_text, '\u{2581}');
+    /// Records the merges made when tokenizing a text
+    pub fn explain(&self, input_text: &str) -> MergeTrace {
+        let (text, _) = self.pre_process_text(input_text, '\u{2581}', self.config.add_dummy_prefix);
+        let mut merge_trace = MergeTrace::new(&text);
+        Self::merge_into(
+            &self.config.merge_table,
+            &text,
+            &mut Workspace::new(),
+            &mut Vec::new(),
+            &mut (),
+            Some(&mut merge_trace),
+        );
+        merge_trace
+    }
 
-        let mut symbols = SymbolList::from_text(text.as_str());
-        let mut agenda: BinaryHeap<SymbolNodePair> = BinaryHeap::new();
+    /// Runs the merges of a table on a text, also used to check which pieces BPE can produce when
+    /// building other tokenizers
+    pub(crate) fn merge_into<R: AgendaRecorder>(
+        merge_table: &MergeTable,
+        text: &str,
+        workspace: &mut Workspace,
+        symbols: &mut Vec<Symbol>,
+        agenda_recorder: &mut R,
+        mut merge_trace: Option<&mut MergeTrace>,
+    ) {
+        let symbol_list = &mut workspace.symbol_list;
+        let agenda = &mut workspace.agenda;
+        symbol_list.reset(text, merge_table);
+        agenda.clear();
 
-        for symbol_index in 1..symbols.len() {
-            self.maybe_add_pair(
+        for symbol_index in 1..symbol_list.len() {
+            Self::maybe_add_pair(
+                merge_table,
                 symbol_index as isize - 1,
                 symbol_index as isize,
-                text.as_str(),
-                &symbols,
-                &mut agenda,
+                symbol_list,
+                agenda,
+                agenda_recorder,
             );
         }
 
@@ -177,38 +221,89 @@ impl BpeTokenizer for PriorityQueueBpeLLTokenizer {
             let left_symbol_index = symbol_pair.left;
             let right_symbol_index = symbol_pair.right;
             if left_symbol_index != -1 && right_symbol_index != -1 {
-                let new_symbol = symbols.merge_symbols(
+                // The start of the right symbol is only needed to trace the merge
+                let right_start = match merge_trace {
+                    Some(_) => symbol_list[right_symbol_index as usize]
+                        .map_or(0, |right_symbol| right_symbol.start_byte),
+                    None => 0,
+                };
+                let new_symbol = symbol_list.merge_symbols(
                     left_symbol_index as usize,
                     right_symbol_index as usize,
                     symbol_pair.pair_size,
+                    merge_table,
                 );
-                if let Some(new_symbol) = new_symbol {
-                    self.maybe_add_pair(
-                        new_symbol.prev,
-                        left_symbol_index,
-                        text.as_str(),
-                        &symbols,
-                        &mut agenda,
-                    );
-                    self.maybe_add_pair(
-                        left_symbol_index,
-                        new_symbol.next,
-                        text.as_str(),
-                        &symbols,
-                        &mut agenda,
-                    );
+                match new_symbol {
+                    Some(new_symbol) => {
+                        if let Some(merge_trace) = merge_trace.as_deref_mut() {
+                            merge_trace.record_merge(
+                                new_symbol.start_byte..right_start,
+                                right_start..new_symbol.end_byte,
+                                symbol_pair.score,
+                                symbol_list
+                                    .symbols
+                                    .iter()
+                                    .flatten()
+                                    .map(|symbol| symbol.start_byte..symbol.end_byte),
+                            );
+                        }
+                        Self::maybe_add_pair(
+                            merge_table,
+                            new_symbol.prev,
+                            left_symbol_index,
+                            symbol_list,
+                            agenda,
+                            agenda_recorder,
+                        );
+                        Self::maybe_add_pair(
+                            merge_table,
+                            left_symbol_index,
+                            new_symbol.next,
+                            symbol_list,
+                            agenda,
+                            agenda_recorder,
+                        );
+                    }
+                    None => agenda_recorder.record_stale(),
                 }
             }
         }
 
-        let mut output = Vec::new();
-        for symbol in symbols {
-            if let Some(symbol) = symbol {
-                output.push(
-                    &input_text[byte_mapping[&symbol.start_byte]..byte_mapping[&symbol.end_byte]],
-                );
-            }
-        }
-        output
+        symbols.extend(symbol_list.symbols.iter().flatten().map(|symbol| Symbol {
+            start_byte: symbol.start_byte,
+            end_byte: symbol.end_byte,
+            id: symbol.id,
+        }));
+    }
+}
+
+impl BpeTokenizer for PriorityQueueBpeLLTokenizer {
+    fn from_config(config: TokenizerConfig) -> Self {
+        Self { config }
+    }
+
+    fn config(&self) -> &TokenizerConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut TokenizerConfig {
+        &mut self.config
+    }
+
+    fn apply_merges(&self, text: &str) -> Vec<Symbol> {
+        let mut symbols = Vec::new();
+        self.apply_merges_into(text, &mut Workspace::default(), &mut symbols);
+        symbols
+    }
+
+    fn apply_merges_into(&self, text: &str, workspace: &mut Workspace, symbols: &mut Vec<Symbol>) {
+        Self::merge_into(
+            &self.config.merge_table,
+            text,
+            workspace,
+            symbols,
+            &mut (),
+            None,
+        );
     }
 }
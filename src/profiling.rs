@@ -0,0 +1,37 @@
+/// Pairs going through the agenda of a priority queue tokenizer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AgendaStats {
+    /// Pairs pushed onto the agenda
+    pub pushed: usize,
+    /// Pairs popped after one of their symbols was merged into another pair
+    pub stale: usize,
+    /// Largest number of pairs on the agenda at once
+    pub peak_len: usize,
+}
+
+/// Receives the agenda events of the priority queue tokenizers. `()` ignores them, so that the
+/// tokenizing path does no bookkeeping.
+pub(crate) trait AgendaRecorder {
+    fn record_push(&mut self, agenda_len: usize);
+
+    fn record_stale(&mut self);
+}
+
+impl AgendaRecorder for () {
+    #[inline(always)]
+    fn record_push(&mut self, _agenda_len: usize) {}
+
+    #[inline(always)]
+    fn record_stale(&mut self) {}
+}
+
+impl AgendaRecorder for AgendaStats {
+    fn record_push(&mut self, agenda_len: usize) {
+        self.pushed += 1;
+        self.peak_len = self.peak_len.max(agenda_len);
+    }
+
+    fn record_stale(&mut self) {
+        self.stale += 1;
+    }
+}
@@ -1 +1,2 @@
-pub mod sentencepiece_model;
\ No newline at end of file
+#[allow(renamed_and_removed_lints, unused_parens, mismatched_lifetime_syntaxes)]
+pub mod sentencepiece_model;
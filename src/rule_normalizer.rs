@@ -0,0 +1,148 @@
+use crate::normalizer::{is_single_whitespace, AlignedChar, NormalizedString, Normalizer};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Default)]
+struct RuleTrieNode {
+    children: HashMap<char, usize>,
+    target: Option<Vec<char>>,
+}
+
+/// Rewrites text with source -> target rules, always applying the longest matching source.
+///
+/// Rules are read from sentencepiece normalization rule TSV files: one rule per line, the source
+/// and target are sequences of space-separated hexadecimal code points separated by a tab (e.g.
+/// `41 302\t1EA6`), an empty target deletes the source. Further columns are ignored. Models do not
+/// need them at runtime, their rules are compiled into the precompiled charsmap.
+pub struct RuleNormalizer {
+    nodes: Vec<RuleTrieNode>,
+    keeps_whitespace: bool,
+}
+
+impl RuleNormalizer {
+    pub fn new(rules: Vec<(String, String)>) -> Self {
+        let mut normalizer = Self {
+            nodes: vec![RuleTrieNode::default()],
+            keeps_whitespace: true,
+        };
+        for (source, target) in rules {
+            normalizer.keeps_whitespace &= !source.contains(char::is_whitespace)
+                || is_single_whitespace(&source) && is_single_whitespace(&target);
+            normalizer.insert(source.chars(), target.chars().collect());
+        }
+        normalizer
+    }
+
+    pub fn from_tsv(tsv_path: &Path) -> io::Result<Self> {
+        Self::from_tsv_str(fs::read_to_string(tsv_path)?.as_str())
+    }
+
+    pub fn from_tsv_str(tsv: &str) -> io::Result<Self> {
+        let mut rules = Vec::new();
+        for (line_index, line) in tsv.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid_line = |message: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {} ({:?})", line_index + 1, message, line),
+                )
+            };
+            let mut fields = line.split('\t');
+            let source = Self::parse_code_points(fields.next().unwrap_or_default())
+                .ok_or_else(|| invalid_line("invalid source code point"))?;
+            let target = Self::parse_code_points(
+                fields
+                    .next()
+                    .ok_or_else(|| invalid_line("missing target column"))?,
+            )
+            .ok_or_else(|| invalid_line("invalid target code point"))?;
+            if source.is_empty() {
+                return Err(invalid_line("empty source"));
+            }
+            rules.push((source, target));
+        }
+        Ok(Self::new(rules))
+    }
+
+    fn parse_code_points(field: &str) -> Option<String> {
+        field
+            .split(' ')
+            .filter(|code_point| !code_point.is_empty())
+            .map(|code_point| {
+                u32::from_str_radix(code_point, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+            })
+            .collect()
+    }
+
+    fn insert<I>(&mut self, source: I, target: Vec<char>)
+    where
+        I: Iterator<Item = char>,
+    {
+        let mut node = 0;
+        for character in source {
+            node = match self.nodes[node].children.get(&character) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(RuleTrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(character, child);
+                    child
+                }
+            };
+        }
+        self.nodes[node].target = Some(target);
+    }
+
+    /// Returns the length (in characters) and target of the longest rule matching `chars`
+    fn longest_match(&self, chars: &[AlignedChar]) -> Option<(usize, &[char])> {
+        let mut node = 0;
+        let mut longest_match = None;
+        for (index, c) in chars.iter().enumerate() {
+            match self.nodes[node].children.get(&c.character) {
+                Some(&child) => node = child,
+                None => break,
+            }
+            if let Some(target) = &self.nodes[node].target {
+                longest_match = Some((index + 1, target.as_slice()));
+            }
+        }
+        longest_match
+    }
+}
+
+impl Normalizer for RuleNormalizer {
+    fn keeps_whitespace(&self) -> bool {
+        self.keeps_whitespace
+    }
+
+    fn normalize(&self, normalized: &mut NormalizedString) {
+        let mut output = Vec::with_capacity(normalized.chars.len());
+        let mut position = 0;
+        while position < normalized.chars.len() {
+            match self.longest_match(&normalized.chars[position..]) {
+                Some((length, target)) => {
+                    let source = &normalized.chars[position..position + length];
+                    let original_start = source.iter().map(|c| c.original_start).min().unwrap();
+                    let original_end = source.iter().map(|c| c.original_end).max().unwrap();
+                    output.extend(target.iter().map(|&character| AlignedChar {
+                        character,
+                        original_start,
+                        original_end,
+                    }));
+                    position += length;
+                }
+                None => {
+                    output.push(normalized.chars[position]);
+                    position += 1;
+                }
+            }
+        }
+        normalized.chars = output;
+    }
+}
@@ -0,0 +1,137 @@
+use crate::bpe_base::BpeTokenizer;
+use std::collections::VecDeque;
+use std::io;
+use std::io::BufRead;
+
+/// Tokenizes a `BufRead` incrementally, yielding the same tokens as `tokenize` on the full text.
+///
+/// Input is read in blocks of at most `chunk_size` bytes until at least `chunk_size` bytes are
+/// buffered, whatever the line lengths. The buffer is then cut before its last whitespace (after it
+/// if whitespace is treated as suffix) and only the part before the cut is tokenized, so memory
+/// stays bounded as long as whitespace keeps coming. This is exact as long as merges never cross a
+/// whitespace token (see `BpeTokenizer::can_split_on_whitespace`).
+///
+/// If any piece of the vocabulary has a whitespace token past its first character (before its
+/// last one in suffix mode), the entire input is read into memory and tokenized at once, so the
+/// tokenizer no longer streams.
+pub struct StreamingTokenizer<'a, T, R>
+where
+    T: BpeTokenizer,
+    R: BufRead,
+{
+    tokenizer: &'a T,
+    reader: R,
+    buffer: Vec<u8>,
+    tokens: VecDeque<String>,
+    chunk_size: usize,
+    split_on_whitespace: bool,
+    treat_whitespace_as_suffix: bool,
+    first_chunk: bool,
+    done: bool,
+}
+
+impl<'a, T, R> StreamingTokenizer<'a, T, R>
+where
+    T: BpeTokenizer,
+    R: BufRead,
+{
+    pub fn new(tokenizer: &'a T, reader: R) -> Self {
+        Self {
+            tokenizer,
+            reader,
+            buffer: Vec::new(),
+            tokens: VecDeque::new(),
+            chunk_size: 64 * 1024,
+            split_on_whitespace: tokenizer.can_split_on_whitespace('\u{2581}'),
+            treat_whitespace_as_suffix: tokenizer.treat_whitespace_as_suffix(),
+            first_chunk: true,
+            done: false,
+        }
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Position of the last whitespace in the buffered text (after it in suffix mode), 0 if none
+    fn find_cut(&self) -> io::Result<usize> {
+        let text = valid_utf8_prefix(&self.buffer)?;
+        Ok(
+            match text.char_indices().rfind(|(_, c)| c.is_whitespace()) {
+                Some((position, c)) if self.treat_whitespace_as_suffix => position + c.len_utf8(),
+                Some((position, _)) => position,
+                None => 0,
+            },
+        )
+    }
+
+    fn tokenize_next_chunk(&mut self) -> io::Result<()> {
+        let mut cut = 0;
+        while cut == 0 && !self.done {
+            let block = self.reader.fill_buf()?;
+            if block.is_empty() {
+                self.done = true;
+            } else {
+                let length = block.len().min(self.chunk_size.max(1));
+                self.buffer.extend_from_slice(&block[..length]);
+                self.reader.consume(length);
+                if self.split_on_whitespace && self.buffer.len() >= self.chunk_size {
+                    cut = self.find_cut()?;
+                }
+            }
+        }
+        if self.done {
+            cut = self.buffer.len();
+        }
+        let text = std::str::from_utf8(&self.buffer[..cut])
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        let add_dummy_whitespace = self.tokenizer.add_dummy_prefix()
+            && if self.treat_whitespace_as_suffix {
+                self.done
+            } else {
+                self.first_chunk
+            };
+        for token in self.tokenizer.tokenize_chunk(text, add_dummy_whitespace) {
+            self.tokens.push_back(token.to_owned());
+        }
+        self.buffer.drain(..cut);
+        self.first_chunk = false;
+        Ok(())
+    }
+}
+
+/// Longest valid UTF-8 prefix of a buffer, which may end in the middle of a character
+fn valid_utf8_prefix(buffer: &[u8]) -> io::Result<&str> {
+    match std::str::from_utf8(buffer) {
+        Ok(text) => Ok(text),
+        Err(error) if error.error_len().is_none() => {
+            Ok(std::str::from_utf8(&buffer[..error.valid_up_to()]).unwrap())
+        }
+        Err(error) => Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+    }
+}
+
+impl<'a, T, R> Iterator for StreamingTokenizer<'a, T, R>
+where
+    T: BpeTokenizer,
+    R: BufRead,
+{
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(token) = self.tokens.pop_front() {
+                return Some(Ok(token));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(error) = self.tokenize_next_chunk() {
+                self.done = true;
+                return Some(Err(error));
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::bpe_base::Symbol;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+struct CacheEntry {
+    word: String,
+    symbols: Vec<Symbol>,
+    referenced: bool,
+}
+
+struct ClockState {
+    index: HashMap<String, usize>,
+    entries: Vec<CacheEntry>,
+    hand: usize,
+}
+
+/// Bounded cache from pre-split words to their merged symbols, with CLOCK eviction.
+///
+/// Symbols are stored relative to the start of the word. The cache can be shared across
+/// threads, lookups and insertions take a lock on the cache state.
+pub struct WordCache {
+    capacity: usize,
+    state: Mutex<ClockState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl WordCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(ClockState {
+                index: HashMap::with_capacity(capacity),
+                entries: Vec::with_capacity(capacity),
+                hand: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Appends the cached symbols of `word` to `symbols`, returns false if it is not cached
+    pub fn get(&self, word: &str, symbols: &mut Vec<Symbol>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if let Some(&position) = state.index.get(word) {
+            let entry = &mut state.entries[position];
+            entry.referenced = true;
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            symbols.extend_from_slice(&entry.symbols);
+            true
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            false
+        }
+    }
+
+    pub fn insert(&self, word: &str, symbols: &[Symbol]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.index.contains_key(word) {
+            return;
+        }
+        let new_entry = CacheEntry {
+            word: word.to_owned(),
+            symbols: symbols.to_vec(),
+            referenced: false,
+        };
+        if state.entries.len() < self.capacity {
+            let position = state.entries.len();
+            state.entries.push(new_entry);
+            state.index.insert(word.to_owned(), position);
+            return;
+        }
+        // Second chance: skip (and clear) recently referenced entries until one can be evicted
+        loop {
+            let hand = state.hand;
+            state.hand = (hand + 1) % self.capacity;
+            if state.entries[hand].referenced {
+                state.entries[hand].referenced = false;
+            } else {
+                let evicted = std::mem::replace(&mut state.entries[hand], new_entry);
+                state.index.remove(&evicted.word);
+                state.index.insert(word.to_owned(), hand);
+                return;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::alignment::Alignment;
+use crate::bpe_base::Symbol;
+use crate::normalizer::NormalizedString;
+use crate::priority_queue_linked_list_bpe::{SymbolList, SymbolNodePair};
+use std::collections::BinaryHeap;
+
+/// Scratch buffers reused across `BpeTokenizer::encode_into` calls.
+///
+/// A workspace can be used with any tokenizer, but not concurrently: serving threads should each
+/// keep their own.
+#[derive(Default)]
+pub struct Workspace {
+    pub(crate) normalized: NormalizedString,
+    pub(crate) text: String,
+    pub(crate) alignment: Alignment,
+    pub(crate) symbols: Vec<Symbol>,
+    pub(crate) symbol_list: SymbolList,
+    pub(crate) agenda: BinaryHeap<SymbolNodePair>,
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
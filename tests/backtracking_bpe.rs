@@ -0,0 +1,76 @@
+use bpe_example::{BacktrackingBpeTokenizer, BpeTokenizer, PriorityQueueBpeLLTokenizer};
+use std::collections::HashMap;
+
+const ALPHABET: [char; 5] = ['a', 'b', 'c', 'é', '\u{2581}'];
+
+/// Deterministic linear congruential generator, good enough to draw vocabularies and texts
+struct Lcg(u64);
+
+impl Lcg {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) % n
+    }
+
+    fn text(&mut self, max_length: u64) -> String {
+        let length = self.below(max_length + 1);
+        (0..length)
+            .map(|_| ALPHABET[self.below(ALPHABET.len() as u64) as usize])
+            .collect()
+    }
+}
+
+fn random_vocab(rng: &mut Lcg) -> HashMap<String, i64> {
+    let mut vocab = HashMap::new();
+    for _ in 0..3 + rng.below(40) {
+        let piece = rng.text(5);
+        if !piece.is_empty() && !vocab.contains_key(&piece) {
+            let id = vocab.len() as i64;
+            vocab.insert(piece, id);
+        }
+    }
+    vocab
+}
+
+fn assert_same_tokens(vocab: HashMap<String, i64>, texts: &[String]) {
+    let backtracking = BacktrackingBpeTokenizer::from_merges_vocab(vocab.clone());
+    let linked_list = PriorityQueueBpeLLTokenizer::from_merges_vocab(vocab.clone());
+    for text in texts {
+        assert_eq!(
+            backtracking.tokenize_chunk(text, false),
+            linked_list.tokenize_chunk(text, false),
+            "text {:?}, vocab {:?}",
+            text,
+            vocab
+        );
+    }
+}
+
+#[test]
+fn matches_priority_queue_on_random_vocabs() {
+    let mut rng = Lcg(42);
+    for _ in 0..2000 {
+        let vocab = random_vocab(&mut rng);
+        let texts: Vec<String> = (0..10).map(|_| rng.text(30)).collect();
+        assert_same_tokens(vocab, &texts);
+    }
+}
+
+#[test]
+fn matches_priority_queue_on_repetitive_text() {
+    let vocab = ["a", "aa", "aaa", "aaaa", "ab", "aab", "b", "ba", "bab"]
+        .iter()
+        .enumerate()
+        .map(|(id, piece)| (piece.to_string(), id as i64))
+        .collect();
+    let texts = vec![
+        "a".repeat(1000),
+        "ab".repeat(500),
+        "aab".repeat(300),
+        format!("{}b", "a".repeat(999)),
+    ];
+    assert_same_tokens(vocab, &texts);
+}
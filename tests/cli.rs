@@ -0,0 +1,262 @@
+#![cfg(feature = "cli")]
+mod common;
+
+use bpe_example::{read_model_proto, ModelProto};
+use common::{fixture_model, fixture_path};
+use protobuf::Message;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output, Stdio};
+
+const TEXT: &str = "Licensed under the Apache License\nunder the terms and conditions\n";
+
+fn model_path() -> PathBuf {
+    fixture_path("bpe.model")
+}
+
+fn run_bpe(args: &[&str], input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bpe"))
+        .args(args)
+        .arg("--model")
+        .arg(model_path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn encode_writes_one_line_per_input_line() {
+    let pieces = run_bpe(&["encode"], TEXT);
+    assert_eq!(pieces.lines().count(), 2);
+    assert!(pieces.starts_with("▁License d ▁under ▁the"));
+}
+
+#[test]
+fn algorithms_give_the_same_ids() {
+    let expected = run_bpe(&["encode", "--format", "ids"], TEXT);
+    for algorithm in ["naive", "priority-queue", "backtracking", "naive-pre-split"].iter() {
+        assert_eq!(
+            run_bpe(
+                &["encode", "--format", "ids", "--algorithm", algorithm],
+                TEXT
+            ),
+            expected
+        );
+    }
+}
+
+#[test]
+fn decode_round_trips_ids_and_jsonl() {
+    let ids = run_bpe(&["encode", "--format", "ids"], TEXT);
+    assert_eq!(run_bpe(&["decode"], &ids), TEXT);
+    let jsonl = run_bpe(&["encode", "--format", "jsonl"], TEXT);
+    assert_eq!(run_bpe(&["decode"], &jsonl), TEXT);
+}
+
+#[test]
+fn jsonl_offsets_cover_the_pieces() {
+    let jsonl = run_bpe(&["encode", "--format", "jsonl"], "the license");
+    let line: serde_json::Value = serde_json::from_str(jsonl.trim_end()).unwrap();
+    let pieces = line["pieces"].as_array().unwrap();
+    assert_eq!(pieces.len(), line["ids"].as_array().unwrap().len());
+    assert_eq!(pieces.len(), line["offsets"].as_array().unwrap().len());
+    assert_eq!(line["offsets"][1], serde_json::json!([3, 11]));
+}
+
+#[test]
+fn whole_input_is_a_single_line() {
+    let ids = run_bpe(&["encode", "--whole", "--format", "ids"], TEXT);
+    assert_eq!(ids.lines().count(), 1);
+}
+
+fn inspect(model_path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bpe"))
+        .arg("inspect")
+        .arg(model_path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn inspect_reports_the_fixture_model() {
+    let output = inspect(&model_path(), &["--strict", "--longest", "3"]);
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains("vocabulary size                380"));
+    assert!(report.contains("\"▁Contribution\""));
+    assert!(report.ends_with("anomalies\n  none\n"));
+}
+
+#[test]
+fn inspect_reports_anomalies() {
+    let mut model_proto = fixture_model();
+    let mut empty_piece = model_proto.get_pieces()[10].clone();
+    empty_piece.set_piece(String::new());
+    let duplicate_piece = model_proto.get_pieces()[20].clone();
+    model_proto.mut_pieces().push(empty_piece);
+    model_proto.mut_pieces().push(duplicate_piece);
+    let model_path = temp_path("inspect.model");
+    fs::write(&model_path, model_proto.write_to_bytes().unwrap()).unwrap();
+
+    let lenient_output = inspect(&model_path, &[]);
+    let strict_output = inspect(&model_path, &["--strict"]);
+    fs::remove_file(&model_path).unwrap();
+
+    assert!(lenient_output.status.success());
+    assert!(!strict_output.status.success());
+    let report = String::from_utf8(strict_output.stdout).unwrap();
+    assert!(report.contains("  piece 380 is empty\n"));
+    assert!(report.contains(&format!(
+        "  piece {:?} appears at ids [20, 381]\n",
+        model_proto.get_pieces()[20].get_piece()
+    )));
+}
+
+fn convert(from: &str, to: &str, source: &Path, target: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bpe"))
+        .args(["convert", "--from", from, "--to", to, "--corpus"])
+        .arg(fixture_path("corpus.txt"))
+        .arg(source)
+        .arg(target)
+        .output()
+        .unwrap()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("bpe-{}-{}", process::id(), name))
+}
+
+#[test]
+fn convert_round_trips_through_every_format() {
+    let tokenizer_path = temp_path("tokenizer.json");
+    let gpt2_path = temp_path("gpt2");
+    let converted_path = temp_path("converted.model");
+    let outputs = vec![
+        convert("sentencepiece", "hf", &model_path(), &tokenizer_path),
+        convert("hf", "gpt2", &tokenizer_path, &gpt2_path),
+        convert("gpt2", "sentencepiece", &gpt2_path, &converted_path),
+    ];
+    let converted_model = read_model_proto(&converted_path).unwrap();
+    fs::remove_file(&tokenizer_path).unwrap();
+    fs::remove_dir_all(&gpt2_path).unwrap();
+    fs::remove_file(&converted_path).unwrap();
+
+    for output in outputs {
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "0 of 2000 lines tokenized differently\n"
+        );
+    }
+    // Scores are not kept by merges-based formats, the ids and types of the pieces are
+    let pieces_and_types = |model_proto: &ModelProto| {
+        model_proto
+            .get_pieces()
+            .iter()
+            .map(|piece| (piece.get_piece().to_owned(), piece.get_field_type()))
+            .collect::<Vec<_>>()
+    };
+    let model = fixture_model();
+    assert_eq!(pieces_and_types(&converted_model), pieces_and_types(&model));
+}
+
+#[test]
+fn convert_reports_differences() {
+    let gpt2_path = temp_path("gpt2-reversed");
+    let converted_path = temp_path("reversed.model");
+    convert("sentencepiece", "gpt2", &model_path(), &gpt2_path);
+    // Reversing the merges makes the ranks disagree with the piece ids the crate merges by
+    let merges_path = gpt2_path.join("merges.txt");
+    let merges = fs::read_to_string(&merges_path).unwrap();
+    let mut lines: Vec<&str> = merges.lines().collect();
+    lines[1..].reverse();
+    fs::write(&merges_path, lines.join("\n")).unwrap();
+
+    let output = convert("gpt2", "sentencepiece", &gpt2_path, &converted_path);
+    fs::remove_dir_all(&gpt2_path).unwrap();
+    fs::remove_file(&converted_path).unwrap();
+
+    assert!(!output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.starts_with("line "));
+    assert!(report.contains("  source: "));
+    assert!(report.contains("  target: "));
+    assert!(!report.ends_with("0 of 2000 lines tokenized differently\n"));
+}
+
+#[test]
+fn convert_tokenizes_byte_level_gpt2_models_by_bytes() {
+    let gpt2_path = temp_path("gpt2-byte-level");
+    let converted_path = temp_path("byte-level.model");
+    let corpus_path = temp_path("byte-level-corpus.txt");
+    fs::create_dir_all(&gpt2_path).unwrap();
+    // `Ġ` stands for the space byte
+    fs::write(
+        gpt2_path.join("vocab.json"),
+        r#"{"h": 0, "i": 1, "Ġ": 2, "hi": 3, "Ġhi": 4, "<unk>": 5}"#,
+    )
+    .unwrap();
+    fs::write(gpt2_path.join("merges.txt"), "#version: 0.2\nh i\nĠ hi\n").unwrap();
+    fs::write(&corpus_path, "hi hi\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_bpe"))
+        .args([
+            "convert",
+            "--from",
+            "gpt2",
+            "--to",
+            "sentencepiece",
+            "--corpus",
+        ])
+        .arg(&corpus_path)
+        .arg(&gpt2_path)
+        .arg(&converted_path)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&gpt2_path).unwrap();
+    fs::remove_file(&converted_path).unwrap();
+    fs::remove_file(&corpus_path).unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("warning: the pieces are byte-level"));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "line 1: \"hi hi\"\n  source: hi Ġhi\n  target: hi <unk> hi\n\
+         1 of 1 lines tokenized differently\n"
+    );
+}
+
+#[test]
+fn convert_warns_when_writing_metaspace_pieces_to_gpt2() {
+    let gpt2_path = temp_path("gpt2-metaspace");
+    let output = convert("sentencepiece", "gpt2", &model_path(), &gpt2_path);
+    fs::remove_dir_all(&gpt2_path).unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("warning: the pieces use `\u{2581}` for spaces"));
+}
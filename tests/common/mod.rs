@@ -0,0 +1,56 @@
+//! Fixtures shared by the integration tests
+#![allow(dead_code)]
+use bpe_example::{read_model_proto, ModelProto};
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+#[path = "../../benches/common/allocations.rs"]
+pub mod allocations;
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+pub fn fixture_model() -> ModelProto {
+    read_model_proto(&fixture_path("bpe.model")).unwrap()
+}
+
+/// Model trained with `treat_whitespace_as_suffix`, its pieces end with the whitespace token
+pub fn suffix_fixture_model() -> ModelProto {
+    read_model_proto(&fixture_path("bpe_suffix.model")).unwrap()
+}
+
+/// Precompiled charsmap rewriting single ASCII bytes.
+///
+/// Every byte is a child of the root (at offset 0) and has its leaf at `byte ^ 0x80`, outside of
+/// the ASCII range, so the Darts double array never has colliding units.
+pub fn precompiled_charsmap(rules: &[(char, &str)]) -> Vec<u8> {
+    let mut trie = vec![0u32; 256];
+    let mut normalized = Vec::new();
+    for &(source, replacement) in rules {
+        let byte = u8::try_from(source).unwrap();
+        assert!(byte != 0 && byte.is_ascii());
+        trie[byte as usize] = byte as u32 | 1 << 8 | 0x80 << 10;
+        trie[(byte ^ 0x80) as usize] = 1 << 31 | normalized.len() as u32;
+        normalized.extend_from_slice(replacement.as_bytes());
+        normalized.push(0);
+    }
+    let mut bytes = ((trie.len() * 4) as u32).to_le_bytes().to_vec();
+    for unit in trie {
+        bytes.extend_from_slice(&unit.to_le_bytes());
+    }
+    bytes.extend_from_slice(&normalized);
+    bytes
+}
+
+/// Charsmap lowercasing ASCII letters
+pub fn lowercase_charsmap() -> Vec<u8> {
+    let lowercase: Vec<(char, String)> = ('A'..='Z')
+        .map(|c| (c, c.to_ascii_lowercase().to_string()))
+        .collect();
+    let rules: Vec<(char, &str)> = lowercase.iter().map(|(c, s)| (*c, s.as_str())).collect();
+    precompiled_charsmap(&rules)
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use bpe_example::{
+    BpeTokenizer, Decoder, ModelProto, NaiveBpeTokenizer, NaivePreSplitBpeTokenizer,
+    PriorityQueueBpeLLTokenizer,
+};
+use common::{fixture_model, lowercase_charsmap, precompiled_charsmap, suffix_fixture_model};
+
+const TEXTS: &[&str] = &[
+    "",
+    "Licensed under the Apache License",
+    "  leading and trailing whitespace ",
+    "tabs\tand\r\nnewlines\n",
+    "literal ▁whitespace▁ tokens",
+];
+
+/// Text as the decoder returns it: whitespace becomes spaces
+fn whitespace_as_spaces(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .collect()
+}
+
+fn assert_round_trips(model_proto: &ModelProto) {
+    let decoder = Decoder::from_model_proto(model_proto).unwrap();
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(model_proto).unwrap();
+    let pre_split = NaivePreSplitBpeTokenizer::from_model_proto(model_proto).unwrap();
+    for text in TEXTS {
+        // The whitespace token only decodes as itself when it is a piece of its own
+        let expected = whitespace_as_spaces(text).replace('▁', " ");
+        assert_eq!(decoder.decode(&tokenizer.encode(text).ids), expected);
+        assert_eq!(decoder.decode(&pre_split.encode(text).ids), expected);
+    }
+}
+
+#[test]
+fn decode_round_trips_encode() {
+    assert_round_trips(&fixture_model());
+}
+
+#[test]
+fn decode_round_trips_encode_in_suffix_mode() {
+    assert_round_trips(&suffix_fixture_model());
+}
+
+#[test]
+fn decode_round_trips_encode_without_dummy_prefix() {
+    let mut model_proto = fixture_model();
+    model_proto
+        .mut_normalizer_spec()
+        .set_add_dummy_prefix(false);
+    let tokenizer = NaiveBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    assert!(!tokenizer.add_dummy_prefix());
+    // Only the second word starts with the whitespace token its pieces are trained with
+    let pieces = tokenizer.tokenize("under under");
+    assert_eq!(pieces, vec!["u", "n", "d", "er", " under"]);
+    assert_round_trips(&model_proto);
+}
+
+#[test]
+fn leading_whitespace_token_is_taken_as_the_dummy_prefix() {
+    let model_proto = fixture_model();
+    let decoder = Decoder::from_model_proto(&model_proto).unwrap();
+    let tokenizer = NaiveBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let ids = tokenizer.encode("\u{2581}under the").ids;
+    assert_eq!(ids, tokenizer.encode("under the").ids);
+    assert_eq!(decoder.decode(&ids), "under the");
+}
+
+#[test]
+fn unknown_characters_decode_as_the_unknown_surface() {
+    let model_proto = fixture_model();
+    let decoder = Decoder::from_model_proto(&model_proto).unwrap();
+    let tokenizer = NaiveBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let ids = tokenizer.encode("under 日本").ids;
+    assert_eq!(decoder.decode(&ids), "under  \u{2047}  \u{2047} ");
+}
+
+#[test]
+fn decode_applies_the_model_denormalizer() {
+    let mut model_proto = fixture_model();
+    model_proto
+        .mut_denormalizer_spec()
+        .set_precompiled_charsmap(precompiled_charsmap(&[('l', "L"), (' ', "_")]));
+    let decoder = Decoder::from_model_proto(&model_proto).unwrap();
+    let tokenizer = NaiveBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let ids = tokenizer.encode("the license file").ids;
+    assert_eq!(decoder.decode(&ids), "the_License_fiLe");
+}
+
+#[test]
+fn decode_inverts_the_model_normalizer_up_to_normalization() {
+    let mut model_proto = fixture_model();
+    model_proto
+        .mut_normalizer_spec()
+        .set_precompiled_charsmap(lowercase_charsmap());
+    let decoder = Decoder::from_model_proto(&model_proto).unwrap();
+    let tokenizer = NaiveBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let ids = tokenizer.encode("The Apache LICENSE").ids;
+    assert_eq!(decoder.decode(&ids), "the apache license");
+}
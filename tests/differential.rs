@@ -0,0 +1,217 @@
+mod common;
+
+use bpe_example::{
+    BacktrackingBpeTokenizer, BpeTokenizer, NaiveBpeTokenizer, NaivePreSplitBpeTokenizer,
+    PreTokenizer, PriorityQueueBpeLLTokenizer, PriorityQueueBpeTokenizer,
+    PriorityQueuePreSplitBpeLLTokenizer, WhitespacePunctuationPreTokenizer,
+};
+use common::{fixture_model, fixture_path};
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+
+/// Every line of the inputs fixture, and the whole file as a single text
+fn inputs() -> Vec<String> {
+    let inputs = fs::read_to_string(fixture_path("inputs.txt")).unwrap();
+    let mut texts: Vec<String> = inputs.lines().map(str::to_owned).collect();
+    texts.push(String::new());
+    texts.push(inputs);
+    texts
+}
+
+/// Tokens as byte ranges of the pre-processed text
+fn merge<T>(tokenizer: &T, text: &str) -> Vec<Range<usize>>
+where
+    T: BpeTokenizer,
+{
+    tokenizer
+        .apply_merges(text)
+        .iter()
+        .map(|symbol| symbol.start_byte..symbol.end_byte)
+        .collect()
+}
+
+/// Smallest span of the text over which two tokenizations differ
+struct Divergence {
+    text: String,
+    token_index: usize,
+    span: Range<usize>,
+    expected: Vec<Range<usize>>,
+    actual: Vec<Range<usize>>,
+}
+
+impl Divergence {
+    fn tokens(&self, tokens: &[Range<usize>]) -> Vec<&str> {
+        tokens
+            .iter()
+            .map(|token| &self.text[token.clone()])
+            .collect()
+    }
+
+    /// Whether an expected token crosses a pre-splitting boundary next to punctuation, in which
+    /// case pre-splitting legitimately prevents the merge
+    fn is_punctuation_split(&self) -> bool {
+        let pre_tokenizer = WhitespacePunctuationPreTokenizer::default();
+        let mut boundary = 0;
+        pre_tokenizer.pre_tokenize(&self.text).iter().any(|word| {
+            boundary += word.len();
+            let next_to_punctuation = self.text[..boundary]
+                .chars()
+                .next_back()
+                .into_iter()
+                .chain(self.text[boundary..].chars().next())
+                .any(|c| c.is_ascii_punctuation());
+            next_to_punctuation
+                && self
+                    .expected
+                    .iter()
+                    .any(|token| token.start < boundary && boundary < token.end)
+        })
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "first diverging token #{} in {:?}: expected {:?}, got {:?}",
+            self.token_index,
+            &self.text[self.span.clone()],
+            self.tokens(&self.expected),
+            self.tokens(&self.actual),
+        )
+    }
+}
+
+/// Spans where the tokenizations differ, in order
+fn divergences(text: &str, expected: &[Range<usize>], actual: &[Range<usize>]) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() && j < actual.len() {
+        if expected[i] == actual[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let (start_i, start_j) = (i, j);
+        let (mut expected_end, mut actual_end) = (expected[i].end, actual[j].end);
+        while expected_end != actual_end {
+            if expected_end < actual_end {
+                i += 1;
+                expected_end = expected[i].end;
+            } else {
+                j += 1;
+                actual_end = actual[j].end;
+            }
+        }
+        i += 1;
+        j += 1;
+        divergences.push(Divergence {
+            text: text.to_owned(),
+            token_index: start_i,
+            span: expected[start_i].start..expected_end,
+            expected: expected[start_i..i].to_vec(),
+            actual: actual[start_j..j].to_vec(),
+        });
+    }
+    divergences
+}
+
+fn assert_equivalent<R, T>(reference: &R, tokenizer: &T, name: &str)
+where
+    R: BpeTokenizer,
+    T: BpeTokenizer,
+{
+    for input in inputs() {
+        let (text, _) = reference.pre_process_text(&input, '\u{2581}', true);
+        let expected = merge(reference, &text);
+        let actual = merge(tokenizer, &text);
+        if let Some(divergence) = divergences(&text, &expected, &actual).first() {
+            panic!("{}: {}", name, divergence);
+        }
+        assert_eq!(
+            reference.encode(&input),
+            tokenizer.encode(&input),
+            "{}: {:?}",
+            name,
+            input
+        );
+    }
+}
+
+/// Checks that pre-splitting only differs where merges would cross punctuation, returning these
+/// known differences
+fn assert_pre_split_equivalent<R, T>(reference: &R, tokenizer: &T, name: &str) -> Vec<Divergence>
+where
+    R: BpeTokenizer,
+    T: BpeTokenizer,
+{
+    let mut punctuation_splits = Vec::new();
+    for input in inputs() {
+        let (text, _) = reference.pre_process_text(&input, '\u{2581}', true);
+        let expected = merge(reference, &text);
+        let actual = merge(tokenizer, &text);
+        for divergence in divergences(&text, &expected, &actual) {
+            if !divergence.is_punctuation_split() {
+                panic!("{}: {}", name, divergence);
+            }
+            punctuation_splits.push(divergence);
+        }
+    }
+    punctuation_splits
+}
+
+fn reference() -> PriorityQueueBpeLLTokenizer {
+    PriorityQueueBpeLLTokenizer::new(&fixture_path("bpe.model")).unwrap()
+}
+
+#[test]
+fn naive_matches_reference() {
+    let tokenizer = NaiveBpeTokenizer::new(&fixture_path("bpe.model")).unwrap();
+    assert_equivalent(&reference(), &tokenizer, "NaiveBpeTokenizer");
+}
+
+#[test]
+fn priority_queue_matches_reference() {
+    let tokenizer = PriorityQueueBpeTokenizer::new(&fixture_path("bpe.model")).unwrap();
+    assert_equivalent(&reference(), &tokenizer, "PriorityQueueBpeTokenizer");
+}
+
+#[test]
+fn backtracking_matches_reference() {
+    let tokenizer = BacktrackingBpeTokenizer::new(&fixture_path("bpe.model")).unwrap();
+    assert_equivalent(&reference(), &tokenizer, "BacktrackingBpeTokenizer");
+}
+
+#[test]
+fn pre_split_only_differs_on_punctuation() {
+    let naive = NaiveBpeTokenizer::new(&fixture_path("bpe.model")).unwrap();
+    let naive_pre_split = NaivePreSplitBpeTokenizer::new(&fixture_path("bpe.model")).unwrap();
+    let punctuation_splits =
+        assert_pre_split_equivalent(&naive, &naive_pre_split, "NaivePreSplitBpeTokenizer");
+    let linked_list_pre_split =
+        PriorityQueuePreSplitBpeLLTokenizer::new(&fixture_path("bpe.model")).unwrap();
+    assert_pre_split_equivalent(
+        &reference(),
+        &linked_list_pre_split,
+        "PriorityQueuePreSplitBpeLLTokenizer",
+    );
+
+    // The fixture has merges such as `▁(` and `s,`, which pre-splitting prevents
+    let first_split = punctuation_splits
+        .first()
+        .expect("the fixture inputs contain merges across punctuation");
+    println!("known pre-split difference, {}", first_split);
+    assert!(punctuation_splits.iter().any(|divergence| divergence
+        .tokens(&divergence.expected)
+        .contains(&"\u{2581}(")));
+}
+
+#[test]
+fn pre_split_by_trainer_spec_matches_reference() {
+    // Without trainer specification the fixture splits by whitespace, script and number, which
+    // its merges never cross
+    let tokenizer =
+        NaivePreSplitBpeTokenizer::from_model_proto_with_trainer_spec(&fixture_model()).unwrap();
+    assert_equivalent(&reference(), &tokenizer, "NaivePreSplitBpeTokenizer");
+}
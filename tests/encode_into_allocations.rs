@@ -0,0 +1,61 @@
+mod common;
+
+use bpe_example::{BpeTokenizer, Encoding, PriorityQueueBpeLLTokenizer, Workspace};
+use common::allocations::{measure_allocations, CountingAllocator};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn steady_state_encoding_does_not_allocate() {
+    let vocab = [
+        "<unk>",
+        "\u{2581}",
+        "t",
+        "h",
+        "e",
+        "\u{2581}t",
+        "th",
+        "he",
+        "the",
+        "\u{2581}the",
+        "a",
+        "n",
+        "an",
+        "d",
+        "and",
+        "\u{2581}and",
+        "c",
+        "at",
+        "cat",
+        "\u{2581}cat",
+    ]
+    .iter()
+    .enumerate()
+    .map(|(id, piece)| (piece.to_string(), id as i64))
+    .collect();
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_merges_vocab(vocab);
+    let texts = [
+        "the cat and the hat",
+        "",
+        "thethethe andand",
+        "caté naïve the",
+        "a much longer text where the cat and the other cat sat on the mat and then ran",
+    ];
+
+    let mut workspace = Workspace::new();
+    let mut encoding = Encoding::default();
+    for text in texts.iter() {
+        tokenizer.encode_into(&mut workspace, text, &mut encoding);
+        assert_eq!(encoding, tokenizer.encode(text));
+    }
+
+    let (_, allocation_stats) = measure_allocations(|| {
+        for _ in 0..10 {
+            for text in texts.iter() {
+                tokenizer.encode_into(&mut workspace, text, &mut encoding);
+            }
+        }
+    });
+    assert_eq!(allocation_stats.allocations, 0);
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use bpe_example::{
+    BacktrackingBpeTokenizer, BpeTokenizer, Encoding, ModelProto, ModelProto_SentencePiece,
+    ModelProto_SentencePiece_Type, NaiveBpeTokenizer, NaivePreSplitBpeTokenizer,
+    PriorityQueueBpeLLTokenizer,
+};
+use common::fixture_model;
+
+/// Encoding with ids `0..length` and one byte per token
+fn encoding(length: usize) -> Encoding {
+    Encoding {
+        ids: (0..length as i64).collect(),
+        offsets: (0..length).map(|start| (start, start + 1)).collect(),
+    }
+}
+
+fn window_ids(windows: &[Encoding]) -> Vec<Vec<i64>> {
+    windows.iter().map(|window| window.ids.clone()).collect()
+}
+
+#[test]
+fn consecutive_windows_share_stride_tokens() {
+    let windows = encoding(10).windows(4, 2);
+    assert_eq!(
+        window_ids(&windows),
+        vec![
+            vec![0, 1, 2, 3],
+            vec![2, 3, 4, 5],
+            vec![4, 5, 6, 7],
+            vec![6, 7, 8, 9]
+        ]
+    );
+    for (window, next_window) in windows.iter().zip(windows.iter().skip(1)) {
+        assert_eq!(window.ids[2..], next_window.ids[..2]);
+        assert_eq!(window.offsets[2..], next_window.offsets[..2]);
+    }
+    let windows = encoding(6).windows(3, 0);
+    assert_eq!(window_ids(&windows), vec![vec![0, 1, 2], vec![3, 4, 5]]);
+}
+
+#[test]
+fn final_window_can_be_partial() {
+    let windows = encoding(9).windows(4, 1);
+    assert_eq!(
+        window_ids(&windows),
+        vec![vec![0, 1, 2, 3], vec![3, 4, 5, 6], vec![6, 7, 8]]
+    );
+    // A short encoding is a single window
+    assert_eq!(encoding(3).windows(4, 1), vec![encoding(3)]);
+    assert_eq!(encoding(4).windows(4, 1), vec![encoding(4)]);
+}
+
+#[test]
+fn empty_encoding_is_a_single_empty_window() {
+    let windows = Encoding::default().windows(4, 1);
+    assert_eq!(windows, vec![Encoding::default()]);
+}
+
+#[test]
+#[should_panic(expected = "stride (4) must be smaller than max_length (4)")]
+fn stride_must_be_smaller_than_max_length() {
+    encoding(10).windows(4, 4);
+}
+
+#[test]
+#[should_panic(expected = "stride (5) must be smaller than max_length (4)")]
+fn stride_larger_than_max_length_is_rejected() {
+    encoding(10).windows(4, 5);
+}
+
+#[test]
+fn window_offsets_map_to_the_source_text() {
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&fixture_model()).unwrap();
+    let text = "Licensed under the Apache License, Version 2.0 (the \"License\");";
+    let encoding = tokenizer.encode(text);
+    let windows = tokenizer.encode_windows(text, 8, 3);
+    assert!(windows.len() > 1);
+    let mut start = 0;
+    for window in windows.iter() {
+        assert!(window.len() <= 8);
+        assert_eq!(*window, encoding.slice(start, start + window.len()));
+        // The tokens of a window tile the source text it spans
+        let tokens: String = window
+            .offsets
+            .iter()
+            .map(|&(token_start, token_end)| &text[token_start..token_end])
+            .collect();
+        let span = window.offsets[0].0..window.offsets[window.len() - 1].1;
+        assert_eq!(tokens, text[span]);
+        start += 8 - 3;
+    }
+    let last_window = windows.last().unwrap();
+    assert_eq!(last_window.offsets.last().unwrap().1, text.len());
+}
+
+/// Model with the pieces `▁`, `a` and `▁a`, and an unknown piece of another name than `<unk>`
+fn unknown_piece_model(unknown_type: ModelProto_SentencePiece_Type) -> ModelProto {
+    let mut model_proto = ModelProto::new();
+    for piece in ["<s>", "\u{2581}", "a", "[UNK]", "\u{2581}a"].iter() {
+        let mut sentence_piece = ModelProto_SentencePiece::new();
+        sentence_piece.set_piece(piece.to_string());
+        if *piece == "[UNK]" {
+            sentence_piece.set_field_type(unknown_type);
+        }
+        model_proto.mut_pieces().push(sentence_piece);
+    }
+    model_proto
+}
+
+#[test]
+fn unknown_characters_get_the_id_of_the_model_unknown_piece() {
+    let model_proto = unknown_piece_model(ModelProto_SentencePiece_Type::UNKNOWN);
+    let encodings = vec![
+        NaiveBpeTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode("a?a"),
+        PriorityQueueBpeLLTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode("a?a"),
+        BacktrackingBpeTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode("a?a"),
+        NaivePreSplitBpeTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode("a?a"),
+    ];
+    for encoding in encodings {
+        assert_eq!(encoding.ids, vec![4, 3, 2]);
+    }
+
+    // Without an UNKNOWN piece, the trainer specification gives the unknown id
+    let mut model_proto = unknown_piece_model(ModelProto_SentencePiece_Type::NORMAL);
+    model_proto.mut_trainer_spec().set_unk_id(3);
+    let tokenizer = NaiveBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    assert_eq!(tokenizer.encode("a?a").ids, vec![4, 3, 2]);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use bpe_example::{
+    BpeTokenizer, MergeTrace, NaiveBpeTokenizer, PriorityQueueBpeLLTokenizer,
+    PriorityQueueBpeTokenizer,
+};
+use common::{fixture_model, fixture_path};
+use std::fs;
+
+fn assert_consistent(merge_trace: &MergeTrace) {
+    let mut symbols = merge_trace.initial_symbols();
+    for step in merge_trace.steps.iter() {
+        assert_eq!(symbols[step.position], step.pair.0);
+        assert_eq!(symbols[step.position + 1], step.pair.1);
+        let merged = symbols.remove(step.position + 1);
+        symbols[step.position].push_str(&merged);
+        assert_eq!(symbols, step.symbols);
+        assert_eq!(
+            merge_trace.text[step.byte_range.clone()],
+            symbols[step.position]
+        );
+    }
+}
+
+#[test]
+fn merge_traces_agree_with_tokenization() {
+    let model_proto = fixture_model();
+    let naive = NaiveBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let b_tree = PriorityQueueBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let linked_list = PriorityQueueBpeLLTokenizer::from_model_proto(&model_proto).unwrap();
+    let inputs = fs::read_to_string(fixture_path("inputs.txt")).unwrap();
+    let mut texts: Vec<&str> = inputs.lines().collect();
+    texts.extend_from_slice(&["", "ssssssssss", "llllll pppp"]);
+
+    for text in texts {
+        let merge_trace = naive.explain(text);
+        assert_consistent(&merge_trace);
+        let (pre_processed_text, _) = naive.pre_process_text(text, '\u{2581}', true);
+        let tokens: Vec<String> = naive
+            .apply_merges(&pre_processed_text)
+            .into_iter()
+            .map(|symbol| pre_processed_text[symbol.byte_range()].to_owned())
+            .collect();
+        assert_eq!(merge_trace.final_symbols(), tokens);
+        assert_eq!(b_tree.explain(text), merge_trace);
+        assert_eq!(linked_list.explain(text), merge_trace);
+    }
+}
+
+#[test]
+fn merge_trace_table() {
+    let model_proto = fixture_model();
+    let merge_trace = NaiveBpeTokenizer::from_model_proto(&model_proto)
+        .unwrap()
+        .explain("the License");
+    let table = merge_trace.to_string();
+    let rows: Vec<&str> = table.lines().collect();
+    assert_eq!(rows.len(), merge_trace.steps.len() + 2);
+    assert!(rows[0].contains("rank") && rows[0].contains("symbols"));
+    assert!(rows[1].ends_with("▁ t h e ▁ L i c e n s e"));
+    assert!(rows[rows.len() - 1].ends_with(&merge_trace.final_symbols().join(" ")));
+}
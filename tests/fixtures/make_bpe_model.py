@@ -0,0 +1,55 @@
+"""Trains a small BPE model and writes it as a sentencepiece ModelProto.
+
+Usage: make_bpe_model.py <corpus> <output.model> <n_merges> [suffix]
+
+Words are whitespace-separated and prefixed with the whitespace token (suffixed if `suffix` is
+given, which also sets `treat_whitespace_as_suffix`). The most frequent pair is merged until
+`n_merges` merges are made. Pieces are <unk>, <s>, </s>, the merges in order and the characters.
+
+tests/fixtures/bpe.model: make_bpe_model.py source.txt bpe.model 300
+tests/fixtures/bpe_suffix.model: make_bpe_model.py corpus.txt bpe_suffix.model 300 suffix
+"""
+import sys, struct, collections
+corpus = open(sys.argv[1], encoding='utf-8').read()
+n_merges = int(sys.argv[3])
+SUFFIX = len(sys.argv) > 4
+words = collections.Counter((w + '▁') if SUFFIX else ('▁' + w) for w in corpus.split())
+vocab = {tuple(w): c for w, c in words.items()}
+merges = []
+for _ in range(n_merges):
+    pairs = collections.Counter()
+    for w, c in vocab.items():
+        for a, b in zip(w, w[1:]):
+            pairs[a, b] += c
+    if not pairs:
+        break
+    (a, b), c = max(pairs.items(), key=lambda x: (x[1], x[0]))
+    if c < 2:
+        break
+    merges.append(a + b)
+    new = {}
+    for w, cnt in vocab.items():
+        out, i = [], 0
+        while i < len(w):
+            if i + 1 < len(w) and w[i] == a and w[i + 1] == b:
+                out.append(a + b); i += 2
+            else:
+                out.append(w[i]); i += 1
+        new[tuple(out)] = new.get(tuple(out), 0) + cnt
+    vocab = new
+chars = sorted(set(''.join(words)), key=lambda ch: (-sum(c for w, c in words.items() if ch in w), ch))
+pieces = [('<unk>', 2), ('<s>', 3), ('</s>', 3)] + [(m, 1) for m in merges if len(m) > 1] + [(ch, 1) for ch in chars]
+def varint(n):
+    out = b''
+    while True:
+        b = n & 0x7f; n >>= 7
+        if n: out += bytes([b | 0x80])
+        else: return out + bytes([b])
+def ld(field, payload): return varint(field << 3 | 2) + varint(len(payload)) + payload
+out = b''
+for i, (p, t) in enumerate(pieces):
+    sp = ld(1, p.encode()) + bytes([0x15]) + struct.pack('<f', -float(i)) + bytes([0x18]) + varint(t)
+    out += ld(1, sp)
+out += ld(2, bytes([0x18, 2]) + (bytes([0xC0, 0x01, 0x01]) if SUFFIX else b''))
+open(sys.argv[2], 'wb').write(out)
+print(len(pieces), 'pieces')
@@ -0,0 +1,30 @@
+"""Writes a pseudo-text corpus for the benches, free of network access and licensing concerns.
+
+Usage: make_corpus.py <text> <output.txt> <n_lines>
+
+Sentences are random walks over the word bigrams of the input text, with a fixed seed so that the
+corpus is reproducible. Lines hold 4 to 20 words, with an empty line every 8 lines on average.
+
+tests/fixtures/corpus.txt: make_corpus.py source.txt corpus.txt 2000
+
+source.txt is the LICENSE and README.md of the repository at the time the fixtures were made.
+"""
+import sys, random, collections
+words = open(sys.argv[1], encoding='utf-8').read().split()
+n_lines = int(sys.argv[3])
+successors = collections.defaultdict(list)
+for a, b in zip(words, words[1:]):
+    successors[a].append(b)
+rng = random.Random(42)
+word = words[0]
+lines = []
+while len(lines) < n_lines:
+    if rng.random() < 0.125:
+        lines.append('')
+        continue
+    line = []
+    for _ in range(rng.randint(4, 20)):
+        line.append(word)
+        word = rng.choice(successors[word]) if successors[word] else rng.choice(words)
+    lines.append(' '.join(line))
+open(sys.argv[2], 'w', encoding='utf-8').write('\n'.join(lines) + '\n')
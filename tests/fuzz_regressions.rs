@@ -0,0 +1,202 @@
+//! Inputs from the `fuzz` targets, which must load and tokenize without panicking.
+mod common;
+
+use bpe_example::{
+    parse_model_proto, BacktrackingBpeTokenizer, BpeTokenizer, Decoder, ModelProto,
+    NaiveBpeTokenizer, NaivePreSplitBpeTokenizer, NormalizationForm, PriorityQueueBpeLLTokenizer,
+    PriorityQueueBpeTokenizer, UnicodeNormalizer,
+};
+use common::fixture_path;
+use std::fs;
+
+const TEXTS: &[&str] = &[
+    "",
+    " ",
+    "\u{2581}",
+    "ab",
+    "a b  ab\u{2581}\u{2581}ba é",
+    "é",
+    "a日",
+    "\u{0}\t\r\n",
+    "ﬁne ＡＢ ①",
+    "Licensed under the (Apache) License, v2.0",
+];
+
+fn push_varint(mut value: u64, bytes: &mut Vec<u8>) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn push_field(field_number: u64, payload: &[u8], bytes: &mut Vec<u8>) {
+    push_varint(field_number << 3 | 2, bytes);
+    push_varint(payload.len() as u64, bytes);
+    bytes.extend_from_slice(payload);
+}
+
+/// Serialized model with the given pieces, as written by a (possibly buggy) trainer
+fn model_bytes(pieces: &[&str]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for piece in pieces {
+        let mut sentence_piece = Vec::new();
+        push_field(1, piece.as_bytes(), &mut sentence_piece);
+        push_field(1, &sentence_piece, &mut bytes);
+    }
+    bytes
+}
+
+/// Serialized model with a denormalizer using the given precompiled charsmap
+fn model_bytes_with_charsmap(pieces: &[&str], precompiled_charsmap: &[u8]) -> Vec<u8> {
+    let mut bytes = model_bytes(pieces);
+    let mut normalizer_spec = Vec::new();
+    push_field(2, precompiled_charsmap, &mut normalizer_spec);
+    push_field(5, &normalizer_spec, &mut bytes);
+    bytes
+}
+
+/// Serialized model whose normalizer and denormalizer name the given rules file
+fn model_bytes_with_rule_tsv(pieces: &[&str], normalization_rule_tsv: &str) -> Vec<u8> {
+    let mut bytes = model_bytes(pieces);
+    let mut normalizer_spec = Vec::new();
+    push_field(6, normalization_rule_tsv.as_bytes(), &mut normalizer_spec);
+    push_field(3, &normalizer_spec, &mut bytes);
+    push_field(5, &normalizer_spec, &mut bytes);
+    bytes
+}
+
+/// Tokenizes every text with every algorithm, as the fuzz targets do
+fn assert_tokenizes(model_proto: &ModelProto) {
+    let linked_list = PriorityQueueBpeLLTokenizer::from_model_proto(model_proto).unwrap();
+    let naive = NaiveBpeTokenizer::from_model_proto(model_proto).unwrap();
+    let b_tree = PriorityQueueBpeTokenizer::from_model_proto(model_proto).unwrap();
+    let backtracking = BacktrackingBpeTokenizer::from_model_proto(model_proto).unwrap();
+    let pre_split = NaivePreSplitBpeTokenizer::from_model_proto(model_proto).unwrap();
+    let normalized = PriorityQueueBpeLLTokenizer::from_model_proto(model_proto)
+        .unwrap()
+        .with_normalizer(UnicodeNormalizer::new(NormalizationForm::NFKC));
+    let decoder = Decoder::from_model_proto(model_proto).ok();
+
+    for text in TEXTS {
+        assert_eq!(linked_list.tokenize(text).concat(), *text);
+        let expected = linked_list.encode(text);
+        assert_eq!(naive.encode(text), expected, "{:?}", text);
+        assert_eq!(b_tree.encode(text), expected, "{:?}", text);
+        assert_eq!(backtracking.encode(text), expected, "{:?}", text);
+        assert_eq!(pre_split.tokenize(text).concat(), *text);
+        for (start, end) in normalized.encode(text).offsets {
+            assert!(text.is_char_boundary(start) && text.is_char_boundary(end));
+        }
+        if let Some(decoder) = &decoder {
+            let mut ids = expected.ids;
+            ids.extend_from_slice(&[-1, -5, i64::MAX]);
+            let _ = decoder.decode(&ids);
+        }
+    }
+}
+
+#[test]
+fn garbage_model_bytes_are_rejected() {
+    for bytes in [
+        &[0xff][..],
+        &[0x0a, 0x80],
+        &[0x0a, 0x05, 0x0a],
+        b"not a model",
+    ] {
+        assert!(parse_model_proto(bytes).is_err(), "{:?}", bytes);
+    }
+}
+
+#[test]
+fn truncated_models_load_or_are_rejected() {
+    let bytes = fs::read(fixture_path("bpe.model")).unwrap();
+    for length in (0..bytes.len()).step_by(97) {
+        if let Ok(model_proto) = parse_model_proto(&bytes[..length]) {
+            assert_tokenizes(&model_proto);
+        }
+    }
+}
+
+#[test]
+fn oversized_piece_length_loads_an_empty_piece() {
+    // rust-protobuf does not check lengths past the end of the input against the input size
+    let model_proto = parse_model_proto(&[0x0a, 0xff, 0xff, 0xff, 0xff, 0x0f]).unwrap();
+    assert_tokenizes(&model_proto);
+}
+
+#[test]
+fn empty_model() {
+    let model_proto = parse_model_proto(&[]).unwrap();
+    assert_tokenizes(&model_proto);
+}
+
+#[test]
+fn empty_and_duplicate_pieces() {
+    let model_proto = parse_model_proto(&model_bytes(&[
+        "",
+        "a",
+        "b",
+        "ab",
+        "a",
+        "",
+        "\u{2581}a",
+        "é日",
+    ]))
+    .unwrap();
+    assert_tokenizes(&model_proto);
+}
+
+#[test]
+fn pieces_ending_with_multi_byte_characters() {
+    let model_proto = parse_model_proto(&model_bytes(&[
+        "é",
+        "aé",
+        "日",
+        "a日",
+        "\u{2581}a",
+        "\u{2581}a日",
+    ]))
+    .unwrap();
+    assert_tokenizes(&model_proto);
+}
+
+#[test]
+fn invalid_precompiled_charsmaps() {
+    let mut oversized_trie = 1024u32.to_le_bytes().to_vec();
+    oversized_trie.extend_from_slice(&[0; 8]);
+    let mut unaligned_trie = 6u32.to_le_bytes().to_vec();
+    unaligned_trie.extend_from_slice(&[0xff; 9]);
+    for precompiled_charsmap in [&[][..], &[1, 0], &oversized_trie, &unaligned_trie] {
+        let bytes = model_bytes_with_charsmap(&["a", "b", "ab"], precompiled_charsmap);
+        assert_tokenizes(&parse_model_proto(&bytes).unwrap());
+    }
+}
+
+#[test]
+fn rule_tsv_paths_are_not_read() {
+    // Reading an endless file would never return
+    let bytes = model_bytes_with_rule_tsv(&["a", "b", "ab"], "/dev/zero");
+    let model_proto = parse_model_proto(&bytes).unwrap();
+    assert_eq!(
+        model_proto
+            .get_normalizer_spec()
+            .get_normalization_rule_tsv(),
+        "/dev/zero"
+    );
+    assert_tokenizes(&model_proto);
+    assert!(Decoder::from_model_proto(&model_proto).is_ok());
+}
+
+#[test]
+fn fixture_model() {
+    assert_tokenizes(&common::fixture_model());
+}
+
+#[test]
+fn very_long_pieces_load_in_linear_time() {
+    // Every split of the piece is looked up, and BPE runs on it to build the backtracking trie
+    let long_piece = "ab".repeat(1 << 16);
+    let bytes = model_bytes(&["<unk>", "a", "b", "ab", "\u{2581}", &long_piece]);
+    assert_tokenizes(&parse_model_proto(&bytes).unwrap());
+}
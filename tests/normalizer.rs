@@ -0,0 +1,233 @@
+mod common;
+
+use bpe_example::{
+    BacktrackingBpeTokenizer, BpeTokenizer, LowercaseNormalizer, NaiveBpeTokenizer,
+    NaivePreSplitBpeTokenizer, NormalizationForm, NormalizedString, Normalizer,
+    PrecompiledNormalizer, PriorityQueueBpeLLTokenizer, PriorityQueueBpeTokenizer,
+    ReplaceNormalizer, RuleNormalizer, SequenceNormalizer, StripAccentsNormalizer,
+    StripControlNormalizer, UnicodeNormalizer,
+};
+use common::{fixture_model, lowercase_charsmap, precompiled_charsmap};
+use unicode_normalization::UnicodeNormalization;
+
+#[test]
+fn precompiled_charsmap_keeps_alignment() {
+    let normalizer =
+        PrecompiledNormalizer::from_bytes(&precompiled_charsmap(&[('A', "aa"), ('-', "")]))
+            .unwrap();
+    let text = "xA-é";
+    let mut normalized = NormalizedString::from_text(text);
+    normalizer.normalize(&mut normalized);
+    assert_eq!(normalized.text(), "xaaé");
+    let sources: Vec<&str> = normalized
+        .chars
+        .iter()
+        .map(|c| &text[c.original_start..c.original_end])
+        .collect();
+    assert_eq!(sources, vec!["x", "A", "A", "é"]);
+}
+
+#[test]
+fn tokenizers_apply_the_model_normalizer() {
+    let expected = NaiveBpeTokenizer::from_model_proto(&fixture_model())
+        .unwrap()
+        .encode("the apache license");
+    let mut model_proto = fixture_model();
+    model_proto
+        .mut_normalizer_spec()
+        .set_precompiled_charsmap(lowercase_charsmap());
+
+    let text = "The APACHE License";
+    let encodings = vec![
+        NaiveBpeTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode(text),
+        PriorityQueueBpeTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode(text),
+        PriorityQueueBpeLLTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode(text),
+        BacktrackingBpeTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode(text),
+        NaivePreSplitBpeTokenizer::from_model_proto(&model_proto)
+            .unwrap()
+            .encode(text),
+    ];
+    for encoding in encodings {
+        assert_eq!(encoding.ids, expected.ids);
+        // Offsets point in the original text, before lowercasing
+        assert_eq!(encoding.offsets, expected.offsets);
+    }
+}
+
+#[test]
+fn invalid_model_charsmap_is_reported() {
+    let mut model_proto = fixture_model();
+    model_proto
+        .mut_normalizer_spec()
+        .set_precompiled_charsmap(vec![1, 0]);
+    assert!(NaiveBpeTokenizer::from_model_proto(&model_proto).is_err());
+    assert!(NaivePreSplitBpeTokenizer::from_model_proto(&model_proto).is_err());
+}
+
+fn rules(rules: &[(&str, &str)]) -> RuleNormalizer {
+    RuleNormalizer::new(
+        rules
+            .iter()
+            .map(|&(source, target)| (source.to_owned(), target.to_owned()))
+            .collect(),
+    )
+}
+
+#[test]
+fn whitespace_is_kept_by_single_character_rewrites() {
+    assert!(LowercaseNormalizer.keeps_whitespace());
+    assert!(rules(&[("A", "a"), ("\u{3000}", " "), ("ﬁ", "f i")]).keeps_whitespace());
+    let precompiled =
+        PrecompiledNormalizer::from_bytes(&precompiled_charsmap(&[('A', "a"), ('\t', " ")]))
+            .unwrap();
+    assert!(precompiled.keeps_whitespace());
+    let mut sequence = SequenceNormalizer::default();
+    sequence.push(LowercaseNormalizer);
+    sequence.push(precompiled);
+    assert!(sequence.keeps_whitespace());
+}
+
+#[test]
+fn whitespace_is_not_kept_by_rewrites_across_or_of_whitespace() {
+    assert!(!rules(&[("of the", "ofthe")]).keeps_whitespace());
+    assert!(!rules(&[(" ", "")]).keeps_whitespace());
+    assert!(!rules(&[(" ", "_")]).keeps_whitespace());
+    assert!(!rules(&[("\t", "  ")]).keeps_whitespace());
+    assert!(
+        !PrecompiledNormalizer::from_bytes(&precompiled_charsmap(&[(' ', "_")]))
+            .unwrap()
+            .keeps_whitespace()
+    );
+    // Regular expressions are not inspected
+    assert!(!ReplaceNormalizer::new("x", "y").unwrap().keeps_whitespace());
+    let mut sequence = SequenceNormalizer::default();
+    sequence.push(LowercaseNormalizer);
+    sequence.push(rules(&[(" ", "")]));
+    assert!(!sequence.keeps_whitespace());
+}
+
+fn normalize(normalizer: &dyn Normalizer, text: &str) -> NormalizedString {
+    let mut normalized = NormalizedString::from_text(text);
+    normalizer.normalize(&mut normalized);
+    normalized
+}
+
+/// Normalized characters with the original text they are aligned to
+fn aligned_chars<'a>(normalized: &NormalizedString, text: &'a str) -> Vec<(char, &'a str)> {
+    normalized
+        .chars
+        .iter()
+        .map(|c| (c.character, &text[c.original_start..c.original_end]))
+        .collect()
+}
+
+#[test]
+fn lowercase_characters_are_aligned_to_their_source() {
+    // 'İ' lowercases to two characters, both aligned to it
+    let text = "AİB";
+    let normalized = normalize(&LowercaseNormalizer, text);
+    assert_eq!(normalized.text(), "ai\u{307}b");
+    assert_eq!(
+        aligned_chars(&normalized, text),
+        vec![('a', "A"), ('i', "İ"), ('\u{307}', "İ"), ('b', "B")]
+    );
+
+    let tokenizer = NaiveBpeTokenizer::from_model_proto(&fixture_model())
+        .unwrap()
+        .with_normalizer(LowercaseNormalizer);
+    let text = "İLICENSE";
+    for (start, end) in tokenizer.encode(text).offsets {
+        assert!(text.is_char_boundary(start) && text.is_char_boundary(end));
+    }
+}
+
+#[test]
+fn stripped_accents_keep_the_alignment_of_their_base_character() {
+    let text = "éa\u{301}ﬁ";
+    let normalized = normalize(&StripAccentsNormalizer, text);
+    assert_eq!(normalized.text(), "eaﬁ");
+    assert_eq!(
+        aligned_chars(&normalized, text),
+        vec![('e', "é"), ('a', "a"), ('ﬁ', "ﬁ")]
+    );
+}
+
+#[test]
+fn stripped_control_characters_are_dropped_from_the_alignment() {
+    let text = "a\u{0}b\tc\u{7f}\n";
+    let normalized = normalize(&StripControlNormalizer, text);
+    assert_eq!(normalized.text(), "ab\tc\n");
+    assert_eq!(
+        aligned_chars(&normalized, text),
+        vec![
+            ('a', "a"),
+            ('b', "b"),
+            ('\t', "\t"),
+            ('c', "c"),
+            ('\n', "\n")
+        ]
+    );
+}
+
+#[test]
+fn replacements_are_aligned_to_the_whole_match() {
+    let text = "a   b c";
+    let normalized = normalize(&ReplaceNormalizer::new(" +", " ").unwrap(), text);
+    assert_eq!(normalized.text(), "a b c");
+    assert_eq!(
+        aligned_chars(&normalized, text),
+        vec![('a', "a"), (' ', "   "), ('b', "b"), (' ', " "), ('c', "c")]
+    );
+    let text = "aéb";
+    let normalized = normalize(&ReplaceNormalizer::new("é", "").unwrap(), text);
+    assert_eq!(
+        aligned_chars(&normalized, text),
+        vec![('a', "a"), ('b', "b")]
+    );
+}
+
+#[test]
+fn sequence_alignment_composes() {
+    let mut sequence = SequenceNormalizer::default();
+    sequence.push(UnicodeNormalizer::new(NormalizationForm::NFKC));
+    sequence.push(LowercaseNormalizer);
+    sequence.push(ReplaceNormalizer::new("fi", "F").unwrap());
+    // 'ﬁ' -> "fi" -> 'F' stays aligned to the ligature through the three normalizers
+    let text = "ﬁX①";
+    let normalized = normalize(&sequence, text);
+    assert_eq!(normalized.text(), "Fx1");
+    assert_eq!(
+        aligned_chars(&normalized, text),
+        vec![('F', "ﬁ"), ('x', "X"), ('1', "①")]
+    );
+}
+
+#[test]
+fn halfwidth_voiced_marks_compose_with_their_base() {
+    let nfkc = UnicodeNormalizer::new(NormalizationForm::NFKC);
+    for text in ["ｶﾞｷﾞ", "ﾊﾟﾝ", "ﾃﾞｰﾀﾍﾞｰｽ", "ｶﾞ\u{301}ｷ"].iter()
+    {
+        assert_eq!(
+            normalize(&nfkc, text).text(),
+            text.nfkc().collect::<String>()
+        );
+        let nfkd = UnicodeNormalizer::new(NormalizationForm::NFKD);
+        assert_eq!(
+            normalize(&nfkd, text).text(),
+            text.nfkd().collect::<String>()
+        );
+    }
+    let text = "ｶﾞｷﾞ";
+    assert_eq!(
+        aligned_chars(&normalize(&nfkc, text), text),
+        vec![('ガ', "ｶﾞ"), ('ギ', "ｷﾞ")]
+    );
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use bpe_example::{BpeTokenizer, ParallelTokenizer, PriorityQueueBpeLLTokenizer, RuleNormalizer};
+use common::{fixture_model, fixture_path, suffix_fixture_model};
+use std::fs;
+
+const MIN_CHUNK_SIZES: &[usize] = &[1, 2, 7, 64, 4 * 1024, 1 << 20];
+
+fn corpus() -> String {
+    let corpus = fs::read_to_string(fixture_path("corpus.txt")).unwrap();
+    let mut text: String = corpus.lines().take(500).collect::<Vec<_>>().join("\n");
+    text.push_str("  \n\n   Licensed\t\t under   \n \n\t the  \r\n    License   ");
+    text
+}
+
+fn assert_parallel_matches_tokenize<T>(tokenizer: &T, text: &str)
+where
+    T: BpeTokenizer + Sync,
+{
+    let expected = tokenizer.tokenize(text);
+    for &min_chunk_size in MIN_CHUNK_SIZES {
+        let tokens = ParallelTokenizer::new(tokenizer)
+            .with_min_chunk_size(min_chunk_size)
+            .tokenize(text);
+        assert!(tokens == expected, "min chunk size {}", min_chunk_size);
+    }
+}
+
+#[test]
+fn parallel_matches_tokenize() {
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&fixture_model()).unwrap();
+    assert_parallel_matches_tokenize(&tokenizer, &corpus());
+    assert_parallel_matches_tokenize(&tokenizer, "");
+    assert_parallel_matches_tokenize(&tokenizer, "   ");
+}
+
+#[test]
+fn parallel_matches_tokenize_in_suffix_mode() {
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&suffix_fixture_model()).unwrap();
+    assert_parallel_matches_tokenize(&tokenizer, &corpus());
+}
+
+#[test]
+fn parallel_matches_tokenize_with_rules_spanning_whitespace() {
+    let normalizer = RuleNormalizer::new(vec![
+        ("of the".to_owned(), "ofthe".to_owned()),
+        (" \n".to_owned(), "\n".to_owned()),
+        ("License ".to_owned(), "License".to_owned()),
+    ]);
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&fixture_model())
+        .unwrap()
+        .with_normalizer(normalizer);
+    // Cutting "of| the" would keep the rule from matching, the text is tokenized in one chunk
+    assert!(!tokenizer.can_split_on_whitespace('\u{2581}'));
+    assert_parallel_matches_tokenize(&tokenizer, &corpus());
+    assert_parallel_matches_tokenize(&tokenizer, &"work of the license ".repeat(100));
+}
+
+#[test]
+fn parallel_matches_tokenize_with_normalizer_keeping_whitespace() {
+    let normalizer = RuleNormalizer::new(vec![
+        ("\u{3000}".to_owned(), " ".to_owned()),
+        ("License".to_owned(), "license".to_owned()),
+    ]);
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&fixture_model())
+        .unwrap()
+        .with_normalizer(normalizer);
+    assert!(tokenizer.can_split_on_whitespace('\u{2581}'));
+    assert_parallel_matches_tokenize(&tokenizer, &corpus().replace("  ", "\u{3000} "));
+}
@@ -0,0 +1,245 @@
+mod common;
+
+use bpe_example::{
+    BpeTokenizer, DigitsPreTokenizer, ModelProto, ModelProto_SentencePiece, NaiveBpeTokenizer,
+    NaivePreSplitBpeTokenizer, NumbersPreTokenizer, PreTokenizer, PunctuationPreTokenizer,
+    RegexPreTokenizer, SequencePreTokenizer, StreamingTokenizer, TrainerSpec,
+    UnicodeScriptPreTokenizer, WhitespacePreTokenizer, WhitespacePunctuationPreTokenizer,
+};
+use common::fixture_model;
+use std::collections::HashMap;
+use unicode_general_category::GeneralCategory;
+
+/// Vocabulary merging `a▁b` across the whitespace token, without dummy prefix
+fn cross_whitespace_tokenizer() -> NaivePreSplitBpeTokenizer {
+    let vocab: HashMap<String, i64> = ["a", "b", "\u{2581}", "a\u{2581}", "a\u{2581}b"]
+        .iter()
+        .enumerate()
+        .map(|(id, piece)| (piece.to_string(), id as i64))
+        .collect();
+    NaivePreSplitBpeTokenizer::from_tokenizer(
+        NaiveBpeTokenizer::from_merges_vocab(vocab).with_dummy_prefix(false),
+    )
+}
+
+#[test]
+fn whitespace_pre_tokenizers_allow_splitting_on_whitespace() {
+    let tokenizer = cross_whitespace_tokenizer();
+    assert!(tokenizer.can_split_on_whitespace('\u{2581}'));
+    let tokenizer =
+        cross_whitespace_tokenizer().with_pre_tokenizer(WhitespacePreTokenizer::default());
+    assert!(tokenizer.can_split_on_whitespace('\u{2581}'));
+    let mut sequence = SequencePreTokenizer::default();
+    sequence.push(WhitespacePreTokenizer::default());
+    sequence.push(UnicodeScriptPreTokenizer);
+    let tokenizer = cross_whitespace_tokenizer().with_pre_tokenizer(sequence);
+    assert!(tokenizer.can_split_on_whitespace('\u{2581}'));
+}
+
+#[test]
+fn other_pre_tokenizers_fall_back_to_the_vocabulary() {
+    // Merges across whitespace are kept within the words of these pre-tokenizers
+    let tokenizer = cross_whitespace_tokenizer().with_pre_tokenizer(UnicodeScriptPreTokenizer);
+    assert!(!tokenizer.can_split_on_whitespace('\u{2581}'));
+    assert_eq!(tokenizer.tokenize("a b"), vec!["a b"]);
+    let tokenizer = cross_whitespace_tokenizer().with_pre_tokenizer(WhitespacePreTokenizer {
+        treat_whitespace_as_suffix: true,
+        ..Default::default()
+    });
+    assert!(!tokenizer.can_split_on_whitespace('\u{2581}'));
+
+    let tokenizer = NaivePreSplitBpeTokenizer::from_model_proto(&fixture_model())
+        .unwrap()
+        .with_pre_tokenizer(RegexPreTokenizer::gpt2());
+    assert!(!RegexPreTokenizer::gpt2().splits_on_whitespace('\u{2581}', false));
+    assert!(tokenizer.can_split_on_whitespace('\u{2581}'));
+}
+
+#[test]
+fn streaming_keeps_merges_across_whitespace() {
+    let tokenizer = cross_whitespace_tokenizer().with_pre_tokenizer(UnicodeScriptPreTokenizer);
+    let text = "b a b a b a b\n".repeat(8);
+    let tokens = StreamingTokenizer::new(&tokenizer, text.as_bytes())
+        .with_chunk_size(4)
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert_eq!(tokens, tokenizer.tokenize(&text));
+}
+
+#[test]
+fn whitespace_pre_tokenizers() {
+    let text = "\u{2581}a,b\u{2581}\u{2581}c";
+    assert_eq!(
+        WhitespacePreTokenizer::default().pre_tokenize(text),
+        vec!["\u{2581}a,b", "\u{2581}", "\u{2581}c"]
+    );
+    assert_eq!(
+        WhitespacePunctuationPreTokenizer::default().pre_tokenize(text),
+        vec!["\u{2581}a", ",", "b", "\u{2581}", "\u{2581}c"]
+    );
+    let suffix = WhitespacePreTokenizer {
+        treat_whitespace_as_suffix: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        suffix.pre_tokenize("a\u{2581}b\u{2581}\u{2581}"),
+        vec!["a\u{2581}", "b\u{2581}", "\u{2581}"]
+    );
+}
+
+#[test]
+fn regex_patterns_are_adapted_to_the_whitespace_token() {
+    let text = "\u{2581}Hello\u{2581}world's\u{2581}\u{2581}\u{2581}test12345\u{2581}!!\u{2581}\u{2581}I'M";
+    // Whitespace runs leave their last token to the following word, as `\s+(?!\S)` does
+    assert_eq!(
+        RegexPreTokenizer::gpt2().pre_tokenize(text),
+        vec![
+            "\u{2581}Hello",
+            "\u{2581}world",
+            "'s",
+            "\u{2581}\u{2581}",
+            "\u{2581}test",
+            "12345",
+            "\u{2581}!!",
+            "\u{2581}",
+            "\u{2581}I",
+            "'",
+            "M"
+        ]
+    );
+    // cl100k groups digits by three and matches contractions case-insensitively
+    assert_eq!(
+        RegexPreTokenizer::cl100k().pre_tokenize(text),
+        vec![
+            "\u{2581}Hello",
+            "\u{2581}world",
+            "'s",
+            "\u{2581}\u{2581}",
+            "\u{2581}test",
+            "123",
+            "45",
+            "\u{2581}!!",
+            "\u{2581}",
+            "\u{2581}I",
+            "'M"
+        ]
+    );
+    assert!(RegexPreTokenizer::new("(").is_err());
+}
+
+#[test]
+fn numbers_are_split_and_grouped() {
+    assert_eq!(
+        NumbersPreTokenizer::default().pre_tokenize("a1234567b89"),
+        vec!["a", "1234567", "b", "89"]
+    );
+    assert_eq!(
+        NumbersPreTokenizer::with_max_group_length(3).pre_tokenize("a1234567b89"),
+        vec!["a", "123", "456", "7", "b", "89"]
+    );
+    assert_eq!(
+        DigitsPreTokenizer.pre_tokenize("\u{2581}v2.10"),
+        vec!["\u{2581}v", "2", ".", "1", "0"]
+    );
+}
+
+#[test]
+fn unicode_scripts_are_split() {
+    // Whitespace and punctuation stay with the previous word, kana are grouped with Han
+    assert_eq!(
+        UnicodeScriptPreTokenizer
+            .pre_tokenize("\u{2581}hello\u{2581}世界カタカナ\u{2581}мир.\u{2581}e\u{301}"),
+        vec![
+            "\u{2581}hello\u{2581}",
+            "世界カタカナ\u{2581}",
+            "мир.\u{2581}",
+            "e\u{301}"
+        ]
+    );
+}
+
+#[test]
+fn punctuation_is_isolated() {
+    assert_eq!(
+        PunctuationPreTokenizer::default().pre_tokenize("a,b—c«d»e"),
+        vec!["a", ",", "b", "—", "c", "«", "d", "»", "e"]
+    );
+    let other_punctuation = PunctuationPreTokenizer {
+        categories: vec![GeneralCategory::OtherPunctuation],
+    };
+    assert_eq!(
+        other_punctuation.pre_tokenize("a,b—c"),
+        vec!["a", ",", "b—c"]
+    );
+}
+
+#[test]
+fn sequence_splits_the_words_of_each_pre_tokenizer() {
+    let mut sequence = SequencePreTokenizer::default();
+    sequence.push(WhitespacePreTokenizer::default());
+    sequence.push(DigitsPreTokenizer);
+    assert_eq!(
+        sequence.pre_tokenize("\u{2581}a12\u{2581}b"),
+        vec!["\u{2581}a", "1", "2", "\u{2581}b"]
+    );
+    assert_eq!(
+        SequencePreTokenizer::default().pre_tokenize("\u{2581}a12"),
+        vec!["\u{2581}a12"]
+    );
+}
+
+#[test]
+fn trainer_spec_flags_select_the_pre_tokenizers() {
+    let text = "\u{2581}abc123\u{2581}мир";
+    let default_spec = SequencePreTokenizer::from_trainer_spec(&TrainerSpec::new());
+    assert_eq!(
+        default_spec.pre_tokenize(text),
+        vec!["\u{2581}abc", "123", "\u{2581}мир"]
+    );
+    let mut trainer_spec = TrainerSpec::new();
+    trainer_spec.set_split_by_whitespace(false);
+    trainer_spec.set_split_by_unicode_script(false);
+    trainer_spec.set_split_by_number(false);
+    trainer_spec.set_split_digits(true);
+    assert_eq!(
+        SequencePreTokenizer::from_trainer_spec(&trainer_spec).pre_tokenize(text),
+        vec!["\u{2581}abc", "1", "2", "3", "\u{2581}мир"]
+    );
+    trainer_spec.set_split_digits(false);
+    assert_eq!(
+        SequencePreTokenizer::from_trainer_spec(&trainer_spec).pre_tokenize(text),
+        vec![text]
+    );
+}
+
+#[test]
+fn pre_split_tokenizers_follow_the_model_trainer_spec() {
+    let mut model_proto = ModelProto::new();
+    for piece in [
+        "<unk>",
+        "\u{2581}",
+        "1",
+        "2",
+        "12",
+        "\u{2581}1",
+        "\u{2581}12",
+    ]
+    .iter()
+    {
+        let mut sentence_piece = ModelProto_SentencePiece::new();
+        sentence_piece.set_piece(piece.to_string());
+        model_proto.mut_pieces().push(sentence_piece);
+    }
+    let tokenize = |model_proto: &ModelProto| {
+        NaivePreSplitBpeTokenizer::from_model_proto_with_trainer_spec(model_proto)
+            .unwrap()
+            .tokenize("12")
+    };
+    // Numbers are split from the dummy whitespace token by default
+    assert_eq!(tokenize(&model_proto), vec!["", "12"]);
+    model_proto.mut_trainer_spec().set_split_digits(true);
+    assert_eq!(tokenize(&model_proto), vec!["", "1", "2"]);
+    model_proto.mut_trainer_spec().set_split_digits(false);
+    model_proto.mut_trainer_spec().set_split_by_number(false);
+    assert_eq!(tokenize(&model_proto), vec!["12"]);
+}
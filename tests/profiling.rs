@@ -0,0 +1,44 @@
+mod common;
+
+use bpe_example::{BpeTokenizer, PriorityQueueBpeLLTokenizer, PriorityQueueBpeTokenizer};
+use common::allocations::{measure_allocations, CountingAllocator};
+use common::{fixture_model, fixture_path};
+use std::fs;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn allocations_are_measured() {
+    let (vector, allocation_stats) = measure_allocations(|| {
+        let temporary = vec![0u8; 1000];
+        drop(temporary);
+        vec![0u8; 600]
+    });
+    assert_eq!(vector.len(), 600);
+    assert_eq!(allocation_stats.allocations, 2);
+    assert_eq!(allocation_stats.peak_bytes, 1000);
+}
+
+#[test]
+fn every_pushed_pair_is_merged_or_stale() {
+    let model_proto = fixture_model();
+    let b_tree = PriorityQueueBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let linked_list = PriorityQueueBpeLLTokenizer::from_model_proto(&model_proto).unwrap();
+    let inputs = fs::read_to_string(fixture_path("inputs.txt")).unwrap();
+    let texts = [
+        inputs.as_str(),
+        "",
+        "ssssssssssssssssssssssssssssssssssssssss",
+        "llllllll pppp sssss",
+    ];
+
+    for text in texts.iter() {
+        let (pre_processed_text, _) = linked_list.pre_process_text(text, '\u{2581}', true);
+        let merges = pre_processed_text.chars().count() - linked_list.tokenize(text).len();
+        let agenda_stats = linked_list.agenda_stats(text);
+        assert_eq!(agenda_stats.pushed, merges + agenda_stats.stale);
+        assert!(agenda_stats.peak_len <= agenda_stats.pushed);
+        assert_eq!(b_tree.agenda_stats(text), agenda_stats);
+    }
+}
@@ -0,0 +1,163 @@
+use bpe_example::{
+    BpeTokenizer, NaiveBpeTokenizer, NaivePreSplitBpeTokenizer, NormalizationForm,
+    NormalizedString, Normalizer, PriorityQueueBpeLLTokenizer, PriorityQueueBpeTokenizer,
+    UnicodeNormalizer,
+};
+use proptest::prelude::*;
+use std::collections::HashMap;
+
+/// Characters the synthetic vocabularies are built from, mixed with arbitrary ones in texts
+fn vocab_char() -> impl Strategy<Value = char> {
+    prop::sample::select(vec!['a', 'b', 'c', 'é', '日', '\u{2581}'])
+}
+
+fn text() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            4 => vocab_char(),
+            1 => Just(' '),
+            1 => any::<char>(),
+        ],
+        0..60,
+    )
+    .prop_map(|characters| characters.into_iter().collect())
+}
+
+/// ASCII texts with whitespace runs, around the 16 byte blocks of the whitespace search
+fn ascii_text() -> impl Strategy<Value = String> {
+    let length = prop_oneof![
+        prop::sample::select(vec![15usize, 16, 17, 31, 32, 33]),
+        0..64usize,
+    ];
+    length.prop_flat_map(|length| {
+        prop::collection::vec(
+            prop::sample::select(vec!['a', 'b', '.', ' ', '\t', '\r', '\n', '\x0b', '\x0c']),
+            length,
+        )
+        .prop_map(|characters| characters.into_iter().collect())
+    })
+}
+
+/// Leaves the text as is, forcing pre-processing through `NormalizedString`
+struct IdentityNormalizer;
+
+impl Normalizer for IdentityNormalizer {
+    fn normalize(&self, _normalized: &mut NormalizedString) {}
+}
+
+/// Random pieces, ranked in generation order
+fn merges_vocab() -> impl Strategy<Value = HashMap<String, i64>> {
+    prop::collection::vec(prop::collection::vec(vocab_char(), 1..5), 0..40).prop_map(|pieces| {
+        let mut vocab = HashMap::new();
+        for piece in pieces {
+            let id = vocab.len() as i64;
+            vocab.entry(piece.into_iter().collect()).or_insert(id);
+        }
+        vocab
+    })
+}
+
+fn assert_tiles_text<T>(tokenizer: &T, input: &str) -> Result<(), TestCaseError>
+where
+    T: BpeTokenizer,
+{
+    let (text, _) = tokenizer.pre_process_text(input, '\u{2581}', true);
+    let mut position = 0;
+    for symbol in tokenizer.apply_merges(&text) {
+        prop_assert_eq!(symbol.start_byte, position);
+        prop_assert!(symbol.start_byte < symbol.end_byte);
+        prop_assert!(text.is_char_boundary(symbol.end_byte));
+        position = symbol.end_byte;
+    }
+    prop_assert_eq!(position, text.len());
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn tokens_concatenate_to_input(vocab in merges_vocab(), input in text()) {
+        let tokenizer = PriorityQueueBpeLLTokenizer::from_merges_vocab(vocab);
+        prop_assert_eq!(tokenizer.tokenize(&input).concat(), input.clone());
+        assert_tiles_text(&tokenizer, &input)?;
+    }
+
+    #[test]
+    fn ascii_fast_path_matches_normalized_string_path(
+        input in ascii_text(),
+        add_dummy_whitespace in any::<bool>(),
+        treat_whitespace_as_suffix in any::<bool>(),
+    ) {
+        let fast = NaiveBpeTokenizer::from_merges_vocab(HashMap::new())
+            .with_whitespace_as_suffix(treat_whitespace_as_suffix);
+        let slow = NaiveBpeTokenizer::from_merges_vocab(HashMap::new())
+            .with_whitespace_as_suffix(treat_whitespace_as_suffix)
+            .with_normalizer(IdentityNormalizer);
+        let (fast_text, fast_alignment) =
+            fast.pre_process_text(&input, '\u{2581}', add_dummy_whitespace);
+        let (slow_text, slow_alignment) =
+            slow.pre_process_text(&input, '\u{2581}', add_dummy_whitespace);
+        prop_assert_eq!(&fast_text, &slow_text);
+        let boundaries: Vec<usize> = fast_text
+            .char_indices()
+            .map(|(position, _)| position)
+            .chain(Some(fast_text.len()))
+            .collect();
+        for (index, &start) in boundaries.iter().enumerate() {
+            for &end in boundaries[index + 1..].iter() {
+                prop_assert_eq!(
+                    fast_alignment.original_range(start, end),
+                    slow_alignment.original_range(start, end),
+                    "{}..{}", start, end
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn normalized_tokens_concatenate_to_normalized_text(vocab in merges_vocab(), input in text()) {
+        let tokenizer = PriorityQueueBpeLLTokenizer::from_merges_vocab(vocab)
+            .with_normalizer(UnicodeNormalizer::new(NormalizationForm::NFKC));
+        assert_tiles_text(&tokenizer, &input)?;
+    }
+
+    #[test]
+    fn offsets_are_char_boundaries(vocab in merges_vocab(), input in text()) {
+        let tokenizer = PriorityQueueBpeLLTokenizer::from_merges_vocab(vocab)
+            .with_normalizer(UnicodeNormalizer::new(NormalizationForm::NFKD));
+        let encoding = tokenizer.encode(&input);
+        prop_assert_eq!(encoding.ids.len(), encoding.offsets.len());
+        for &(start, end) in encoding.offsets.iter() {
+            prop_assert!(start <= end && end <= input.len());
+            prop_assert!(input.is_char_boundary(start) && input.is_char_boundary(end));
+        }
+    }
+
+    #[test]
+    fn tokenization_is_stable(vocab in merges_vocab(), input in text()) {
+        let tokenizer = NaivePreSplitBpeTokenizer::from_merges_vocab(vocab).with_cache(16);
+        let first = tokenizer.tokenize(&input);
+        prop_assert_eq!(first, tokenizer.tokenize(&input));
+    }
+
+    #[test]
+    fn priority_queue_implementations_agree(vocab in merges_vocab(), input in text()) {
+        let linked_list = PriorityQueueBpeLLTokenizer::from_merges_vocab(vocab.clone());
+        let b_tree = PriorityQueueBpeTokenizer::from_merges_vocab(vocab.clone());
+        let naive = NaiveBpeTokenizer::from_merges_vocab(vocab);
+        let expected = naive.encode(&input);
+        prop_assert_eq!(&linked_list.encode(&input), &expected);
+        prop_assert_eq!(&b_tree.encode(&input), &expected);
+    }
+
+    #[test]
+    fn priority_queue_implementations_agree_on_repeated_characters(
+        vocab in merges_vocab(),
+        character in vocab_char(),
+        count in 0..300usize,
+    ) {
+        let input = character.to_string().repeat(count);
+        let linked_list = PriorityQueueBpeLLTokenizer::from_merges_vocab(vocab.clone());
+        let b_tree = PriorityQueueBpeTokenizer::from_merges_vocab(vocab);
+        prop_assert_eq!(linked_list.encode(&input), b_tree.encode(&input));
+    }
+}
@@ -0,0 +1,95 @@
+use bpe_example::{NormalizedString, Normalizer, RuleNormalizer};
+use std::env;
+use std::fs;
+use std::process;
+
+fn rules(rules: &[(&str, &str)]) -> RuleNormalizer {
+    RuleNormalizer::new(
+        rules
+            .iter()
+            .map(|&(source, target)| (source.to_owned(), target.to_owned()))
+            .collect(),
+    )
+}
+
+fn normalize(normalizer: &RuleNormalizer, text: &str) -> NormalizedString {
+    let mut normalized = NormalizedString::from_text(text);
+    normalizer.normalize(&mut normalized);
+    normalized
+}
+
+/// Normalized characters with the original text they are aligned to
+fn aligned_chars<'a>(normalized: &NormalizedString, text: &'a str) -> Vec<(char, &'a str)> {
+    normalized
+        .chars
+        .iter()
+        .map(|c| (c.character, &text[c.original_start..c.original_end]))
+        .collect()
+}
+
+#[test]
+fn longest_match_is_applied() {
+    let normalizer = rules(&[("a", "x"), ("ab", "y"), ("abc", "z")]);
+    assert_eq!(normalize(&normalizer, "abcab a abd").text(), "zy x yd");
+}
+
+#[test]
+fn overlapping_rules_apply_left_to_right_in_a_single_pass() {
+    let normalizer = rules(&[("ab", "1"), ("bc", "2"), ("1", "3")]);
+    assert_eq!(normalize(&normalizer, "abc").text(), "1c");
+    assert_eq!(normalize(&normalizer, "xbcab").text(), "x21");
+    // Rewritten text is not matched again
+    let normalizer = rules(&[("a", "b"), ("b", "c")]);
+    assert_eq!(normalize(&normalizer, "ab").text(), "bc");
+}
+
+#[test]
+fn empty_target_deletes_the_source() {
+    let normalizer = rules(&[("\u{200b}", "")]);
+    assert_eq!(normalize(&normalizer, "a\u{200b}b\u{200b}").text(), "ab");
+}
+
+#[test]
+fn rewritten_characters_are_aligned_to_the_matched_source() {
+    let text = "xﬁy ab";
+    let normalizer = rules(&[("ﬁ", "fi"), ("ab", "Z"), ("y", "")]);
+    let normalized = normalize(&normalizer, text);
+    assert_eq!(normalized.text(), "xfi Z");
+    assert_eq!(
+        aligned_chars(&normalized, text),
+        vec![('x', "x"), ('f', "ﬁ"), ('i', "ﬁ"), (' ', " "), ('Z', "ab")]
+    );
+}
+
+#[test]
+fn tsv_rules_are_code_points() {
+    let normalizer =
+        RuleNormalizer::from_tsv_str("# comment\n\n41 302\t1EA6\t# Â -> Ầ\n200B\t\n61\t62 63\n")
+            .unwrap();
+    assert_eq!(
+        normalize(&normalizer, "A\u{302}\u{200b}a").text(),
+        "\u{1ea6}bc"
+    );
+}
+
+#[test]
+fn invalid_tsv_lines_are_reported() {
+    for (tsv, message) in [
+        ("41\t42\n41", "line 2: missing target column"),
+        ("41\t42\nzz\t42", "line 2: invalid source code point"),
+        ("41\t110000", "line 1: invalid target code point"),
+        ("\t42", "line 1: empty source"),
+    ] {
+        let error = RuleNormalizer::from_tsv_str(tsv).err().unwrap();
+        assert!(error.to_string().starts_with(message), "{}", error);
+    }
+}
+
+#[test]
+fn tsv_files_are_loaded_explicitly() {
+    let tsv_path = env::temp_dir().join(format!("bpe-rules-{}.tsv", process::id()));
+    fs::write(&tsv_path, "41\t61\n").unwrap();
+    let normalizer = RuleNormalizer::from_tsv(&tsv_path);
+    fs::remove_file(&tsv_path).unwrap();
+    assert_eq!(normalize(&normalizer.unwrap(), "ABA").text(), "aBa");
+}
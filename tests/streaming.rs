@@ -0,0 +1,165 @@
+mod common;
+
+use bpe_example::{
+    BpeTokenizer, NaiveBpeTokenizer, PriorityQueueBpeLLTokenizer, StreamingTokenizer,
+};
+use common::{fixture_model, fixture_path, suffix_fixture_model};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Read};
+use std::rc::Rc;
+
+const CHUNK_SIZES: &[usize] = &[1, 2, 3, 5, 8, 13, 64, 1024, 1 << 20];
+
+/// Corpus lines followed by whitespace runs long enough to straddle chunk boundaries
+fn text() -> String {
+    let corpus = fs::read_to_string(fixture_path("corpus.txt")).unwrap();
+    let mut text: String = corpus.lines().take(50).collect::<Vec<_>>().join("\n");
+    text.push_str("  \n\n   Licensed\t\t under   \n \n\t the  \r\n    License   \n\n\n");
+    text.push_str("trailing words without newline  ");
+    text
+}
+
+fn stream<T>(tokenizer: &T, text: &str, chunk_size: usize) -> Vec<String>
+where
+    T: BpeTokenizer,
+{
+    StreamingTokenizer::new(tokenizer, text.as_bytes())
+        .with_chunk_size(chunk_size)
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn assert_streams_as_tokenize<T>(tokenizer: &T)
+where
+    T: BpeTokenizer,
+{
+    for text in [text().as_str(), "", "   ", "\n\n", "word", " word  \n"] {
+        let expected = tokenizer.tokenize(text);
+        for &chunk_size in CHUNK_SIZES {
+            assert_eq!(
+                stream(tokenizer, text, chunk_size),
+                expected,
+                "chunk size {} for {:?}",
+                chunk_size,
+                text
+            );
+        }
+    }
+}
+
+#[test]
+fn streaming_matches_tokenize() {
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&fixture_model()).unwrap();
+    assert!(tokenizer.can_split_on_whitespace('\u{2581}'));
+    assert_streams_as_tokenize(&tokenizer);
+}
+
+#[test]
+fn streaming_matches_tokenize_in_suffix_mode() {
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&suffix_fixture_model()).unwrap();
+    assert_streams_as_tokenize(&tokenizer);
+}
+
+#[test]
+fn streaming_matches_tokenize_without_dummy_prefix() {
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&fixture_model())
+        .unwrap()
+        .with_dummy_prefix(false);
+    assert_streams_as_tokenize(&tokenizer);
+}
+
+#[test]
+fn vocabulary_with_interior_whitespace_is_streamed_whole() {
+    let vocab: HashMap<String, i64> = [
+        "a",
+        "b",
+        "\u{2581}",
+        "\u{2581}a",
+        "\u{2581}a\u{2581}",
+        "\u{2581}a\u{2581}b",
+    ]
+    .iter()
+    .enumerate()
+    .map(|(id, piece)| (piece.to_string(), id as i64))
+    .collect();
+    let tokenizer = NaiveBpeTokenizer::from_merges_vocab(vocab);
+    assert!(!tokenizer.can_split_on_whitespace('\u{2581}'));
+    let text = "a b a b\na b\n";
+    assert_eq!(tokenizer.tokenize(text)[..2], ["a b", " a b"]);
+    assert_streams_as_tokenize(&tokenizer);
+}
+
+/// Reader counting the bytes consumed from it
+struct CountingReader<'a> {
+    bytes: &'a [u8],
+    consumed: Rc<Cell<usize>>,
+}
+
+impl Read for CountingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.bytes.read(buf)?;
+        self.consumed.set(self.consumed.get() + length);
+        Ok(length)
+    }
+}
+
+impl BufRead for CountingReader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.bytes)
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.bytes.consume(amount);
+        self.consumed.set(self.consumed.get() + amount);
+    }
+}
+
+#[test]
+fn input_without_newlines_is_read_in_bounded_blocks() {
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&fixture_model()).unwrap();
+    let corpus = fs::read_to_string(fixture_path("corpus.txt")).unwrap();
+    let text = corpus.split_whitespace().collect::<Vec<_>>().join(" ");
+    assert!(!text.contains('\n') && text.len() > 64 * 1024);
+
+    let chunk_size = 1024;
+    let consumed = Rc::new(Cell::new(0));
+    let reader = CountingReader {
+        bytes: text.as_bytes(),
+        consumed: Rc::clone(&consumed),
+    };
+    let mut tokens = Vec::new();
+    let mut tokenized_length = 0;
+    let mut max_read_ahead = 0;
+    for token in StreamingTokenizer::new(&tokenizer, reader).with_chunk_size(chunk_size) {
+        let token = token.unwrap();
+        tokenized_length += token.len();
+        max_read_ahead = max_read_ahead.max(consumed.get() - tokenized_length);
+        tokens.push(token);
+    }
+    assert_eq!(tokens, tokenizer.tokenize(&text));
+    assert!(max_read_ahead <= 2 * chunk_size, "{}", max_read_ahead);
+}
+
+#[test]
+fn multi_byte_characters_are_not_cut_between_blocks() {
+    let tokenizer = PriorityQueueBpeLLTokenizer::from_model_proto(&fixture_model()).unwrap();
+    let text = "é日本 ü €€ 😀 ".repeat(50);
+    for &chunk_size in CHUNK_SIZES {
+        assert_eq!(
+            stream(&tokenizer, &text, chunk_size),
+            tokenizer.tokenize(&text),
+            "chunk size {}",
+            chunk_size
+        );
+    }
+    let invalid: &[u8] = b"ab \xff cd";
+    let results: Vec<_> = StreamingTokenizer::new(&tokenizer, invalid)
+        .with_chunk_size(2)
+        .collect();
+    assert_eq!(
+        results.last().unwrap().as_ref().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+}
@@ -0,0 +1,101 @@
+//! Tokenization with a model trained with `treat_whitespace_as_suffix`
+mod common;
+
+use bpe_example::{
+    BacktrackingBpeTokenizer, BpeTokenizer, Decoder, NaiveBpeTokenizer, NaivePreSplitBpeTokenizer,
+    ParallelTokenizer, PriorityQueueBpeLLTokenizer, PriorityQueueBpeTokenizer, StreamingTokenizer,
+};
+use common::{fixture_path, suffix_fixture_model};
+use std::fs;
+
+fn corpus() -> String {
+    let corpus = fs::read_to_string(fixture_path("corpus.txt")).unwrap();
+    let mut text: String = corpus.lines().take(300).collect::<Vec<_>>().join("\n");
+    text.push_str("  \n\n   Licensed\t\t under   \n \n\t the  \r\n    License   ");
+    text
+}
+
+fn tokenizer() -> PriorityQueueBpeLLTokenizer {
+    PriorityQueueBpeLLTokenizer::from_model_proto(&suffix_fixture_model()).unwrap()
+}
+
+#[test]
+fn words_end_with_whitespace() {
+    let tokenizer = tokenizer();
+    assert!(tokenizer.treat_whitespace_as_suffix());
+    // The dummy whitespace token ends the last word
+    assert_eq!(
+        tokenizer.tokenize("Licensed under the License"),
+        vec!["Licens", "ed ", "under ", "the ", "License"]
+    );
+    let (text, _) = tokenizer.pre_process_text("the License", '\u{2581}', true);
+    assert_eq!(text, "the\u{2581}License\u{2581}");
+    let encoding = tokenizer.encode("the License");
+    assert_eq!(encoding.offsets, vec![(0, 4), (4, 11)]);
+}
+
+#[test]
+fn algorithms_agree() {
+    let model_proto = suffix_fixture_model();
+    let naive = NaiveBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let b_tree = PriorityQueueBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let backtracking = BacktrackingBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    let linked_list = tokenizer();
+    for line in corpus().lines() {
+        let expected = naive.encode(line);
+        assert_eq!(b_tree.encode(line), expected, "{:?}", line);
+        assert_eq!(backtracking.encode(line), expected, "{:?}", line);
+        assert_eq!(linked_list.encode(line), expected, "{:?}", line);
+    }
+    let pre_split = NaivePreSplitBpeTokenizer::from_model_proto(&model_proto).unwrap();
+    assert_eq!(
+        pre_split.tokenize("under the License"),
+        naive.tokenize("under the License")
+    );
+}
+
+#[test]
+fn decode_round_trips_encode() {
+    let decoder = Decoder::from_model_proto(&suffix_fixture_model()).unwrap();
+    let tokenizer = tokenizer();
+    for text in [
+        "",
+        "Licensed under the License",
+        "  leading and trailing whitespace ",
+        "tabs\tand\r\nnewlines\n",
+    ] {
+        let expected: String = text
+            .chars()
+            .map(|c| if c.is_whitespace() { ' ' } else { c })
+            .collect();
+        assert_eq!(decoder.decode(&tokenizer.encode(text).ids), expected);
+    }
+}
+
+#[test]
+fn streaming_matches_tokenize() {
+    let tokenizer = tokenizer();
+    assert!(tokenizer.can_split_on_whitespace('\u{2581}'));
+    let text = corpus();
+    let expected = tokenizer.tokenize(&text);
+    for &chunk_size in [1, 3, 16, 1024, 1 << 20].iter() {
+        let tokens = StreamingTokenizer::new(&tokenizer, text.as_bytes())
+            .with_chunk_size(chunk_size)
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap();
+        assert_eq!(tokens, expected, "chunk size {}", chunk_size);
+    }
+}
+
+#[test]
+fn parallel_matches_tokenize() {
+    let tokenizer = tokenizer();
+    let text = corpus();
+    let expected = tokenizer.tokenize(&text);
+    for &min_chunk_size in [1, 7, 64, 4 * 1024].iter() {
+        let tokens = ParallelTokenizer::new(&tokenizer)
+            .with_min_chunk_size(min_chunk_size)
+            .tokenize(&text);
+        assert!(tokens == expected, "min chunk size {}", min_chunk_size);
+    }
+}
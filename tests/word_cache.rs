@@ -0,0 +1,86 @@
+mod common;
+
+use bpe_example::{
+    BpeTokenizer, CacheStats, NaiveBpeTokenizer, PriorityQueuePreSplitBpeLLTokenizer, WordCache,
+};
+use common::{fixture_model, fixture_path};
+use std::fs;
+
+#[test]
+fn cached_tokenizer_matches_uncached_tokenizer() {
+    let model_proto = fixture_model();
+    let uncached = PriorityQueuePreSplitBpeLLTokenizer::from_model_proto(&model_proto).unwrap();
+    let cached = PriorityQueuePreSplitBpeLLTokenizer::from_model_proto(&model_proto)
+        .unwrap()
+        .with_cache(64);
+    let corpus = fs::read_to_string(fixture_path("corpus.txt")).unwrap();
+    // The second pass tokenizes with a full cache
+    for _ in 0..2 {
+        for line in corpus.lines().take(300) {
+            assert_eq!(cached.encode(line), uncached.encode(line), "{:?}", line);
+        }
+    }
+    let stats = cached.cache_stats().unwrap();
+    assert!(stats.hits > 0 && stats.misses > 0);
+    assert_eq!(uncached.cache_stats(), None);
+}
+
+#[test]
+fn cache_stats_count_hits_and_misses() {
+    let tokenizer = PriorityQueuePreSplitBpeLLTokenizer::from_model_proto(&fixture_model())
+        .unwrap()
+        .with_cache(16);
+    assert_eq!(
+        tokenizer.cache_stats(),
+        Some(CacheStats { hits: 0, misses: 0 })
+    );
+    tokenizer.tokenize("the license the license the");
+    let stats = tokenizer.cache_stats().unwrap();
+    assert_eq!(stats, CacheStats { hits: 3, misses: 2 });
+    assert_eq!(stats.hit_rate(), 0.6);
+    assert_eq!(CacheStats { hits: 0, misses: 0 }.hit_rate(), 0.0);
+}
+
+#[test]
+fn cached_symbols_are_appended_to_the_buffer() {
+    let tokenizer = NaiveBpeTokenizer::from_model_proto(&fixture_model()).unwrap();
+    let symbols = tokenizer.apply_merges("\u{2581}license");
+    let cache = WordCache::new(4);
+    cache.insert("\u{2581}license", &symbols);
+
+    let mut buffer = tokenizer.apply_merges("\u{2581}the");
+    let n_symbols = buffer.len();
+    assert!(cache.get("\u{2581}license", &mut buffer));
+    assert_eq!(buffer[n_symbols..], symbols[..]);
+    assert!(!cache.get("\u{2581}the", &mut buffer));
+    assert_eq!(buffer.len(), n_symbols + symbols.len());
+}
+
+#[test]
+fn clock_evicts_unreferenced_entries_at_capacity() {
+    let cache = WordCache::new(2);
+    let is_cached = |word| cache.get(word, &mut Vec::new());
+    cache.insert("a", &[]);
+    cache.insert("b", &[]);
+    assert_eq!(cache.len(), 2);
+    // "a" is referenced and gets a second chance, "b" is evicted
+    assert!(is_cached("a"));
+    cache.insert("c", &[]);
+    assert_eq!(cache.len(), 2);
+    assert!(!is_cached("b"));
+    assert!(is_cached("c"));
+    // "a" used its second chance, "c" was referenced since
+    cache.insert("d", &[]);
+    assert!(!is_cached("a"));
+    assert!(is_cached("c"));
+    assert!(is_cached("d"));
+    assert_eq!(cache.stats(), CacheStats { hits: 4, misses: 2 });
+}
+
+#[test]
+fn zero_capacity_cache_stores_nothing() {
+    let cache = WordCache::new(0);
+    cache.insert("a", &[]);
+    assert!(cache.is_empty());
+    assert!(!cache.get("a", &mut Vec::new()));
+}